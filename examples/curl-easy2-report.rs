@@ -69,12 +69,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         .no_body()
         .push(Extension::Hierarchy)
         .push_other("testing[=]".into(), "0[=:=]0".into());
-    let mut apicall = ApiCall::builder(&svc);
-    let apicall = apicall
-        .transactions(&txns)
+    let apicall = ApiCall::builder(&svc)
+        .report()
+        .transactions(&txns)?
         .extensions(&extensions)
-        .kind(Kind::Report)
-        .build()?;
+        .build();
     let request = Request::from(&apicall);
 
     println!("apicall: {:#?}", apicall);
@@ -0,0 +1,121 @@
+use threescalers::{
+    api_call::*,
+    application::*,
+    credentials::*,
+    extensions::{self, Extension},
+    http::{request::SetupRequest, Request},
+    service::*,
+    transaction::Transaction,
+    usage::Usage,
+};
+
+use std::error::Error;
+
+use ureq::{Agent, Response};
+
+use threescalers::http::request::ureq::UreqRequest;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let creds = Credentials::ServiceToken(ServiceToken::from("12[3]token"));
+    let svc = Service::new("svc123", creds);
+    let uks = [
+        "userkey_1",
+        "userkey_2",
+        "userkey_3",
+        "userkey 4",
+        "userkey 5",
+    ];
+    let apps = uks
+        .iter()
+        .map(|uk| Application::from(UserKey::from(*uk)))
+        .collect::<Vec<_>>();
+
+    println!("Apps: {:#?}", apps);
+
+    let usages = [
+        ("metric11", 11),
+        ("metric12", 12),
+        ("metric21", 21),
+        ("metric22", 22),
+        ("metric31", 31),
+        ("metric32", 32),
+        ("metric41", 41),
+        ("metric42", 42),
+        ("metric51", 51),
+        ("metric52", 52),
+    ]
+    .iter()
+    .map(|m| (m.0, format!("{}", m.1)))
+    .collect::<Vec<_>>();
+
+    let usages = usages.chunks(2).map(Usage::from).collect::<Vec<_>>();
+
+    println!("Usages: {:#?}", usages);
+
+    let ts = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|st| {
+        std::convert::TryInto::<i64>::try_into(st.as_secs())
+            .expect("cannot fit timestamp in an i64")
+    });
+
+    let txns = apps
+        .iter()
+        .zip(&usages)
+        .map(|(a, u)| Transaction::new(a, None, Some(u), ts))
+        .collect::<Vec<_>>();
+
+    let extensions = extensions::List::new()
+        .no_body()
+        .push(Extension::Hierarchy)
+        .push_other("testing[=]".into(), "0[=:=]0".into());
+    let apicall = ApiCall::builder(&svc)
+        .report()
+        .transactions(&txns)?
+        .extensions(&extensions)
+        .build();
+    let request = Request::from(&apicall);
+
+    println!("apicall: {:#?}", apicall);
+    println!("request: {:#?}", request);
+
+    let _ = run_request(request);
+
+    Ok(())
+}
+
+fn run_request(request: Request) -> Result<Response, Box<dyn Error>> {
+    let mut agent = Agent::new();
+    let request = agent.setup_request(request, "https://echo-api.3scale.net")?;
+    let result = exec_request(request);
+    show_response(result).map_err(Into::into)
+}
+
+#[allow(clippy::result_large_err)]
+fn exec_request(request: UreqRequest) -> Result<Response, ureq::Error> {
+    println!("UreqRequest: {:#?}", request);
+    request.call()
+}
+
+#[allow(clippy::result_large_err)]
+fn show_response(res: Result<Response, ureq::Error>) -> Result<Response, ureq::Error> {
+    match res {
+        Ok(response) => {
+            println!("*** SUCCESS ***\n{:#?}", response);
+            // Response#into_string consumes the response, so build a new one out of the body
+            let status = response.status();
+            let status_text = response.status_text().to_owned();
+            let body = response.into_string()?;
+            let jsonval = serde_json::from_str::<serde_json::Value>(&body).unwrap();
+            println!(
+                "*** BODY ***\n{}",
+                serde_json::to_string_pretty(&jsonval).unwrap()
+            );
+            Ok(Response::new(status, &status_text, &body)?)
+        }
+        Err(e) => {
+            println!("*** ERROR ***\n{:#?}", e);
+            Err(e)
+        }
+    }
+}
@@ -9,6 +9,12 @@ use crate::{
 
 use crate::ToParams;
 
+use std::borrow::Cow;
+
+mod validation;
+
+pub use validation::{ValidationError, ValidationIssue};
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Kind {
     Authorize,
@@ -22,79 +28,307 @@ impl Kind {
     pub fn is_report(self) -> bool {
         matches!(self, Kind::Report)
     }
+
+    /// Whether sending a call of this kind more than once has the same effect as sending it once.
+    ///
+    /// Only authorizations are idempotent, since authreps and reports increment usage counters.
+    pub fn is_idempotent(self) -> bool {
+        matches!(self, Kind::Authorize)
+    }
+
+    /// Whether a call of this kind with the given application and user goes to an OAuth endpoint.
+    pub fn is_oauth(self, application: Option<&Application>, user: Option<&User>) -> bool {
+        !self.is_report()
+            && (matches!(application, Some(Application::OAuthToken(_)))
+                || matches!(user, Some(User::OAuthToken(_))))
+    }
 }
 
+/// An API call either borrows its data or owns it, in which case it can be converted into an
+/// `ApiCall<'static>` to be queued or moved into another task.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ApiCall<'a> {
     kind: Kind,
-    service: &'a Service,
-    transactions: &'a [Transaction<'a>],
-    extensions: Option<&'a List<'a>>,
+    service: Cow<'a, Service>,
+    transactions: Cow<'a, [Transaction<'a>]>,
+    extensions: Option<Cow<'a, List<'a>>>,
+}
+
+/// Type states used by `Builder` to only allow building well-formed calls.
+///
+/// A `Builder` starts without a kind and without transactions. Setting the kind determines how
+/// many transactions can be attached: authorize and authrep calls take exactly one, whereas
+/// reports take one or more. Only once both have been set is `build` made available.
+pub mod state {
+    use super::Kind;
+    use crate::transaction::Transaction;
+
+    mod private {
+        pub trait Sealed {}
+    }
+
+    /// Implemented by the type states that represent a `Kind`.
+    pub trait KindState: private::Sealed + Copy {
+        const KIND: Kind;
+    }
+
+    /// Implemented by the kinds that take exactly one transaction.
+    pub trait SingleTransaction: KindState {}
+
+    /// The kind of the call has not been set yet.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct NoKind;
+
+    /// The call is an authorize call.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct Authorize;
+
+    /// The call is an authrep call.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct AuthRep;
+
+    /// The call is a report call.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct Report;
+
+    /// No transactions have been set yet.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct NoTransactions;
+
+    /// A non-empty list of transactions has been set.
+    #[repr(transparent)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct Transactions<'a>(pub(super) &'a [Transaction<'a>]);
+
+    impl private::Sealed for Authorize {}
+    impl private::Sealed for AuthRep {}
+    impl private::Sealed for Report {}
+
+    impl KindState for Authorize {
+        const KIND: Kind = Kind::Authorize;
+    }
+
+    impl KindState for AuthRep {
+        const KIND: Kind = Kind::AuthRep;
+    }
+
+    impl KindState for Report {
+        const KIND: Kind = Kind::Report;
+    }
+
+    impl SingleTransaction for Authorize {}
+    impl SingleTransaction for AuthRep {}
 }
 
+use state::{KindState, NoKind, NoTransactions, SingleTransaction, Transactions};
+
+/// Builds an `ApiCall` checking at compile time that the kind is set and that it is given a
+/// valid number of transactions.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::{api_call::*, application::*, credentials::*, service::*, transaction::*};
+///
+/// let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+/// let app = Application::from_user_key("my_user_key");
+/// let txn = Transaction::new(&app, None, None, None);
+///
+/// let apicall = ApiCall::builder(&service).authorize().transaction(&txn).build();
+///
+/// assert_eq!(apicall.kind(), Kind::Authorize);
+/// ```
+///
+/// Calls without a kind or without transactions cannot be built:
+///
+/// ```compile_fail
+/// use threescalers::{api_call::*, credentials::*, service::*};
+///
+/// let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+///
+/// let apicall = ApiCall::builder(&service).authorize().build();
+/// ```
+///
+/// And neither can authorize and authrep calls with multiple transactions:
+///
+/// ```compile_fail
+/// use threescalers::{api_call::*, application::*, credentials::*, service::*, transaction::*};
+///
+/// let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+/// let app = Application::from_user_key("my_user_key");
+/// let txns = [Transaction::new(&app, None, None, None), Transaction::new(&app, None, None, None)];
+///
+/// let apicall = ApiCall::builder(&service).authrep().transactions(&txns);
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Builder<'a> {
+pub struct Builder<'a, K = NoKind, T = NoTransactions> {
     service: &'a Service,
-    kind: Option<Kind>,
-    transactions: &'a [Transaction<'a>],
+    kind: K,
+    transactions: T,
     extensions: Option<&'a List<'a>>,
 }
 
-// TODO: we can improve this with a state machine of types so that we are required to set svc, app,
-// user and kind before being able to set (required) the usage to build the call
 impl<'a> Builder<'a> {
     pub fn new(service: &'a Service) -> Self {
         Builder {
             service,
-            kind: Default::default(),
-            transactions: Default::default(),
+            kind: NoKind,
+            transactions: NoTransactions,
             extensions: Default::default(),
         }
     }
+}
 
-    pub fn service(&mut self, s: &'a Service) -> &mut Self {
+impl<'a, K, T> Builder<'a, K, T> {
+    pub fn service(mut self, s: &'a Service) -> Self {
         self.service = s;
         self
     }
 
-    pub fn kind(&mut self, t: Kind) -> &mut Self {
-        self.kind = Some(t);
+    pub fn extensions(mut self, extensions: &'a List) -> Self {
+        self.extensions = Some(extensions);
         self
     }
 
-    pub fn transactions(&mut self, txns: &'a [Transaction]) -> &mut Self {
-        self.transactions = txns;
-        self
+    fn with_state<NK, NT>(self, kind: NK, transactions: NT) -> Builder<'a, NK, NT> {
+        Builder {
+            service: self.service,
+            kind,
+            transactions,
+            extensions: self.extensions,
+        }
     }
+}
 
-    pub fn extensions(&mut self, extensions: &'a List) -> &mut Self {
-        self.extensions = Some(extensions);
-        self
+impl<'a> Builder<'a, NoKind, NoTransactions> {
+    pub fn authorize(self) -> Builder<'a, state::Authorize, NoTransactions> {
+        self.with_state(state::Authorize, NoTransactions)
     }
 
-    pub fn build(&self) -> Result<ApiCall, Error> {
-        let kind = self.kind.ok_or_else(|| anyhow!("kind error"))?;
-        Ok(ApiCall::new(
-            kind,
-            self.service,
-            self.transactions,
-            self.extensions,
-        ))
+    pub fn authrep(self) -> Builder<'a, state::AuthRep, NoTransactions> {
+        self.with_state(state::AuthRep, NoTransactions)
+    }
+
+    pub fn report(self) -> Builder<'a, state::Report, NoTransactions> {
+        self.with_state(state::Report, NoTransactions)
     }
 }
 
-use std::borrow::Cow;
+impl<'a, K: SingleTransaction> Builder<'a, K, NoTransactions> {
+    pub fn transaction(self, txn: &'a Transaction<'a>) -> Builder<'a, K, Transactions<'a>> {
+        let kind = self.kind;
+        self.with_state(kind, Transactions(core::slice::from_ref(txn)))
+    }
+}
+
+impl<'a> Builder<'a, state::Report, NoTransactions> {
+    pub fn transaction(
+        self,
+        txn: &'a Transaction<'a>,
+    ) -> Builder<'a, state::Report, Transactions<'a>> {
+        self.with_state(state::Report, Transactions(core::slice::from_ref(txn)))
+    }
+
+    /// Sets the transactions to be reported, failing if there are none.
+    pub fn transactions(
+        self,
+        txns: &'a [Transaction<'a>],
+    ) -> Result<Builder<'a, state::Report, Transactions<'a>>, Error> {
+        if txns.is_empty() {
+            return Err(anyhow!("a report call requires at least one transaction"));
+        }
+
+        Ok(self.with_state(state::Report, Transactions(txns)))
+    }
+}
+
+impl<'a, K: KindState> Builder<'a, K, Transactions<'a>> {
+    pub fn build(&self) -> ApiCall<'a> {
+        // the type states guarantee a valid number of transactions
+        ApiCall::from_parts(
+            K::KIND,
+            Cow::Borrowed(self.service),
+            Cow::Borrowed(self.transactions.0),
+            self.extensions.map(Cow::Borrowed),
+        )
+    }
+}
+
+// Authorize and authrep calls take exactly one transaction, and reports take at least one.
+fn check_transactions(kind: Kind, count: usize) -> Result<(), Error> {
+    match count {
+        0 if kind.is_report() => Err(anyhow!("a report call requires at least one transaction")),
+        1 => Ok(()),
+        count if !kind.is_report() => Err(anyhow!(
+            "{:?} calls take a single transaction but {} were given",
+            kind,
+            count
+        )),
+        _ => Ok(()),
+    }
+}
 
 impl<'a> ApiCall<'a> {
-    pub fn builder(service: &'a Service) -> Builder {
+    pub fn builder(service: &'a Service) -> Builder<'a> {
         Builder::new(service)
     }
 
+    /// Creates an `ApiCall`, failing if the number of transactions is not valid for its kind.
+    ///
+    /// Prefer `ApiCall::builder`, which checks this at compile time.
     pub fn new(
         kind: Kind,
         service: &'a Service,
-        transactions: &'a [Transaction],
-        extensions: Option<&'a List>,
+        transactions: &'a [Transaction<'a>],
+        extensions: Option<&'a List<'a>>,
+    ) -> Result<Self, Error> {
+        check_transactions(kind, transactions.len())?;
+
+        Ok(Self::from_parts(
+            kind,
+            Cow::Borrowed(service),
+            Cow::Borrowed(transactions),
+            extensions.map(Cow::Borrowed),
+        ))
+    }
+
+    /// Creates an `ApiCall` taking ownership of its data, failing if the number of transactions
+    /// is not valid for its kind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::{api_call::*, application::*, credentials::*, service::*, transaction::*};
+    ///
+    /// fn owned_call(user_key: &str) -> ApiCall<'static> {
+    ///     let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+    ///     let app = Application::from_user_key(user_key);
+    ///     let txn = Transaction::new_owned(app, None, None, None);
+    ///
+    ///     ApiCall::new_owned(Kind::Authorize, service, vec![txn], None).unwrap()
+    /// }
+    /// ```
+    pub fn new_owned(
+        kind: Kind,
+        service: Service,
+        transactions: Vec<Transaction<'a>>,
+        extensions: Option<List<'a>>,
+    ) -> Result<Self, Error> {
+        check_transactions(kind, transactions.len())?;
+
+        Ok(Self::from_parts(
+            kind,
+            Cow::Owned(service),
+            Cow::Owned(transactions),
+            extensions.map(Cow::Owned),
+        ))
+    }
+
+    fn from_parts(
+        kind: Kind,
+        service: Cow<'a, Service>,
+        transactions: Cow<'a, [Transaction<'a>]>,
+        extensions: Option<Cow<'a, List<'a>>>,
     ) -> Self {
         Self {
             kind,
@@ -104,16 +338,34 @@ impl<'a> ApiCall<'a> {
         }
     }
 
+    /// Converts the call into one owning all of its data, cloning it if needed.
+    pub fn into_owned(self) -> ApiCall<'static> {
+        ApiCall {
+            kind: self.kind,
+            service: Cow::Owned(self.service.into_owned()),
+            transactions: Cow::Owned(
+                self.transactions
+                    .into_owned()
+                    .into_iter()
+                    .map(Transaction::into_owned)
+                    .collect(),
+            ),
+            extensions: self
+                .extensions
+                .map(|extensions| Cow::Owned(extensions.into_owned().into_owned())),
+        }
+    }
+
     pub fn kind(&self) -> Kind {
         self.kind
     }
 
     pub fn service(&self) -> &Service {
-        self.service
+        self.service.as_ref()
     }
 
     pub fn transactions(&self) -> &[Transaction<'a>] {
-        self.transactions
+        self.transactions.as_ref()
     }
 
     // helper to get a transaction only if it's the only one
@@ -141,7 +393,7 @@ impl<'a> ApiCall<'a> {
     }
 
     pub fn extensions(&self) -> Option<&List> {
-        self.extensions
+        self.extensions.as_deref()
     }
 
     pub fn params(&self) -> Vec<(Cow<'_, str>, &str)> {
@@ -162,7 +414,7 @@ where
         extendable: &mut E,
         key_mangling: &mut F,
     ) {
-        self.service
+        self.service()
             .to_params_with_mangling(extendable, key_mangling);
 
         // keep the borrowck happy about stack closures living long enough
@@ -181,12 +433,26 @@ where
                 &mut txfn_storage_rest
             };
 
-        // having multiple transactions with non-report endpoints
-        // is not allowed, but we can't fail in this trait impl
-        // (plus it would make sense to allow transactions in the
-        // other endpoints).
-        for (e, tx) in self.transactions().iter().enumerate() {
+        // non-report calls take a single transaction, which is checked when creating the call, but
+        // never emit more than one for them just in case
+        let txns = self.transactions();
+        let txns = if self.kind().is_report() {
+            txns
+        } else {
+            &txns[..txns.len().min(1)]
+        };
+
+        for (e, tx) in txns.iter().enumerate() {
             tx.to_params_with_mangling(extendable, &mut |c| key_mangling(e, c));
         }
+
+        if !self.kind().is_report() {
+            if let Some(tx) = self.transaction() {
+                let oauth = self.kind().is_oauth(self.application(), self.user());
+                tx.authorization_params_with_mangling(oauth, extendable, &mut |c| {
+                    key_mangling(0, c)
+                });
+            }
+        }
     }
 }
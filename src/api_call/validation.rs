@@ -0,0 +1,289 @@
+use std::prelude::v1::*;
+
+use std::fmt;
+
+use crate::{application::Application, transaction::Transaction, user::User};
+
+use super::{ApiCall, Kind};
+
+/// A structural problem found in an `ApiCall` that Apisonator would reject.
+///
+/// Issues related to a transaction carry its index in the call's transactions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    /// A report call has no usage, or only empty ones, in any of its transactions.
+    ReportWithoutUsage,
+    /// An OAuth user is used with an application not identified by an OAuth token.
+    OAuthUserWithoutOAuthApplication { transaction: usize },
+    /// A metric name in a usage is empty.
+    EmptyMetricName { transaction: usize },
+    /// A usage value is not a valid increment or set value.
+    InvalidUsageValue {
+        transaction: usize,
+        metric: String,
+        value: String,
+    },
+    /// A timestamp is set in a non-report call.
+    TimestampNotAllowed { kind: Kind, transaction: usize },
+}
+
+impl ValidationIssue {
+    /// The parameter the issue refers to in a call of the given kind, named as it is sent.
+    pub fn field(&self, kind: Kind) -> String {
+        use ValidationIssue::*;
+
+        match self {
+            ReportWithoutUsage => transaction_field(kind, None, "usage"),
+            OAuthUserWithoutOAuthApplication { transaction } => {
+                transaction_field(kind, Some(*transaction), "access_token")
+            }
+            EmptyMetricName { transaction } => {
+                transaction_field(kind, Some(*transaction), "usage[]")
+            }
+            InvalidUsageValue {
+                transaction,
+                metric,
+                ..
+            } => transaction_field(kind, Some(*transaction), &format!("usage[{}]", metric)),
+            TimestampNotAllowed { transaction, .. } => {
+                transaction_field(kind, Some(*transaction), "timestamp")
+            }
+        }
+    }
+}
+
+// Mangles the key of a transaction parameter the same way `ToParams` does for the kind of call.
+fn transaction_field(kind: Kind, transaction: Option<usize>, key: &str) -> String {
+    if !kind.is_report() {
+        return key.into();
+    }
+
+    match transaction {
+        Some(transaction) => format!("transactions[{}]{}", transaction, key),
+        None => format!("transactions[]{}", key),
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ValidationIssue::*;
+
+        match self {
+            ReportWithoutUsage => f.write_str("report without usage in any transaction"),
+            OAuthUserWithoutOAuthApplication { .. } => {
+                f.write_str("OAuth user used with a non-OAuth application")
+            }
+            EmptyMetricName { .. } => f.write_str("empty metric name"),
+            InvalidUsageValue { value, .. } => write!(f, "invalid usage value {:?}", value),
+            TimestampNotAllowed { kind, .. } => {
+                write!(f, "timestamps are not allowed in {:?} calls", kind)
+            }
+        }
+    }
+}
+
+/// The list of issues found when validating an `ApiCall`. It is never empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    kind: Kind,
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidationError {
+    /// The kind of the call the issues were found in.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn issues(&self) -> &[ValidationIssue] {
+        self.issues.as_slice()
+    }
+
+    /// The parameters each issue refers to, in the same order.
+    pub fn fields(&self) -> impl Iterator<Item = String> + '_ {
+        self.issues.iter().map(move |issue| issue.field(self.kind))
+    }
+
+    pub fn into_inner(self) -> Vec<ValidationIssue> {
+        self.issues
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid API call: ")?;
+
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}: {}", issue.field(self.kind), issue)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+impl ApiCall<'_> {
+    /// Checks whether Apisonator would accept this call, returning all the issues found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::{api_call::*, application::*, credentials::*, service::*, transaction::*};
+    ///
+    /// let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+    /// let app = Application::from_user_key("my_user_key");
+    /// let txns = [Transaction::new(&app, None, None, Some(1_600_000_000))];
+    /// let apicall = ApiCall::new(Kind::Authorize, &service, &txns, None)?;
+    ///
+    /// let error = apicall.validate().unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error.issues(),
+    ///     &[ValidationIssue::TimestampNotAllowed { kind: Kind::Authorize, transaction: 0 }]
+    /// );
+    /// # Ok::<(), threescalers::Error>(())
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        use ValidationIssue::*;
+
+        let kind = self.kind();
+        let txns = self.transactions();
+        let mut issues = Vec::new();
+
+        let has_usage = |t: &Transaction| match t.usage() {
+            Some(usage) => !usage.as_vec().is_empty(),
+            None => false,
+        };
+        if kind.is_report() && !txns.iter().any(has_usage) {
+            issues.push(ReportWithoutUsage);
+        }
+
+        for (transaction, txn) in txns.iter().enumerate() {
+            if let Some(User::OAuthToken(_)) = txn.user() {
+                if !matches!(txn.application(), Application::OAuthToken(_)) {
+                    issues.push(OAuthUserWithoutOAuthApplication { transaction });
+                }
+            }
+
+            if !kind.is_report() && txn.timestamp().is_some() {
+                issues.push(TimestampNotAllowed { kind, transaction });
+            }
+
+            for mu in txn.usage().iter().flat_map(|u| u.as_vec().iter()) {
+                if mu.metric().is_empty() {
+                    issues.push(EmptyMetricName { transaction });
+                } else if mu.usage_value().is_err() {
+                    issues.push(InvalidUsageValue {
+                        transaction,
+                        metric: mu.metric().to_owned(),
+                        value: mu.value().to_owned(),
+                    });
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { kind, issues })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures::service, transaction::Transaction, usage::Usage};
+
+    #[test]
+    fn valid_calls() {
+        let service = service();
+        let app = Application::from_app_id("an_app_id");
+        let metrics = [("hits", "1"), ("storage", "#10")];
+        let usage = Usage::new(&metrics);
+        let txns = [Transaction::new(&app, None, Some(&usage), None)];
+
+        for &kind in [Kind::Authorize, Kind::AuthRep, Kind::Report].iter() {
+            assert_eq!(
+                Ok(()),
+                ApiCall::new(kind, &service, &txns, None)
+                    .unwrap()
+                    .validate()
+            );
+        }
+    }
+
+    #[test]
+    fn reports_all_issues() {
+        let service = service();
+        let app = Application::from_app_id("an_app_id");
+        let user = User::from_oauth_token("a_token");
+        let metrics = [("", "1"), ("hits", "-1")];
+        let usage = Usage::new(&metrics);
+        let txns = [Transaction::new(&app, Some(&user), Some(&usage), Some(1))];
+        let apicall = ApiCall::new(Kind::AuthRep, &service, &txns, None).unwrap();
+
+        let error = apicall.validate().unwrap_err();
+        let issues = error.issues();
+
+        assert_eq!(
+            vec![
+                ValidationIssue::OAuthUserWithoutOAuthApplication { transaction: 0 },
+                ValidationIssue::TimestampNotAllowed {
+                    kind: Kind::AuthRep,
+                    transaction: 0
+                },
+                ValidationIssue::EmptyMetricName { transaction: 0 },
+                ValidationIssue::InvalidUsageValue {
+                    transaction: 0,
+                    metric: "hits".into(),
+                    value: "-1".into(),
+                },
+            ]
+            .as_slice(),
+            issues
+        );
+        assert_eq!(
+            vec!["access_token", "timestamp", "usage[]", "usage[hits]"],
+            error.fields().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                "transactions[0]access_token",
+                "transactions[0]timestamp",
+                "transactions[0]usage[]",
+                "transactions[0]usage[hits]",
+            ],
+            issues
+                .iter()
+                .map(|issue| issue.field(Kind::Report))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn report_without_usage() {
+        let service = service();
+        let app = Application::from_user_key("a_user_key");
+        let metrics: [(&str, &str); 0] = [];
+        let usage = Usage::new(&metrics);
+        let txns = [
+            Transaction::new(&app, None, None, Some(1)),
+            Transaction::new(&app, None, Some(&usage), None),
+        ];
+        let apicall = ApiCall::new(Kind::Report, &service, &txns, None).unwrap();
+        let error = apicall.validate().unwrap_err();
+
+        assert_eq!(&[ValidationIssue::ReportWithoutUsage], error.issues());
+        assert_eq!(
+            "invalid API call: transactions[]usage: report without usage in any transaction",
+            error.to_string()
+        );
+    }
+}
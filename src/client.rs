@@ -0,0 +1,482 @@
+//! High level clients sending calls for a service to Apisonator.
+//!
+//! A `Client` puts together the backend base URL, the `Service` and the default extensions, and
+//! sends each call through a `Transport`, returning typed responses. `AsyncClient` mirrors it
+//! for `AsyncTransport`s.
+//!
+//! # Examples
+//!
+//! ```
+//! use threescalers::{
+//!     application::*,
+//!     client::*,
+//!     credentials::*,
+//!     http::{
+//!         response::{FromResponseParts, ResponseError},
+//!         HeaderMap, Request,
+//!     },
+//!     service::*,
+//!     transaction::*,
+//! };
+//!
+//! // a transport that authorizes everything without sending anything
+//! struct AlwaysAuthorized;
+//!
+//! impl Transport for AlwaysAuthorized {
+//!     fn send<R: FromResponseParts>(
+//!         &mut self,
+//!         _request: Request,
+//!         _base_url: &str,
+//!     ) -> Result<R, ResponseError> {
+//!         let body = "<status><authorized>true</authorized><plan>Basic</plan></status>";
+//!
+//!         R::from_response_parts(200, &HeaderMap::new(), Some(body)).map_err(ResponseError::Protocol)
+//!     }
+//! }
+//!
+//! let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+//! let mut client = Client::new(AlwaysAuthorized, "https://su1.3scale.net", service);
+//!
+//! let app = Application::from_user_key("my_user_key");
+//! let txn = Transaction::new(&app, None, None, None);
+//!
+//! assert!(client.authrep(&txn).unwrap().is_authorized());
+//! ```
+use std::prelude::v1::*;
+
+use crate::{
+    api_call::{ApiCall, Kind},
+    extensions::List,
+    http::{
+        endpoints::{Endpoint, Endpoints},
+        response::{FromResponseParts, ResponseError, ResponseFuture},
+        Request,
+    },
+    response::{AuthorizationResponse, ReportResponse},
+    service::Service,
+    transaction::Transaction,
+    Error,
+};
+
+pub mod retry;
+mod transport;
+
+/// Sends requests to Apisonator and reads the responses.
+///
+/// Failures to set up a request are reported as transport errors.
+pub trait Transport {
+    fn send<R: FromResponseParts>(
+        &mut self,
+        request: Request,
+        base_url: &str,
+    ) -> Result<R, ResponseError>;
+
+    /// Sends the request of a call of the given kind. Clients use this method, so that transports
+    /// such as `Retry` can behave differently for each kind of call.
+    fn send_call<R: FromResponseParts>(
+        &mut self,
+        _kind: Kind,
+        request: Request,
+        base_url: &str,
+    ) -> Result<R, ResponseError> {
+        self.send(request, base_url)
+    }
+}
+
+/// Sends requests to Apisonator and reads the responses asynchronously.
+///
+/// Failures to set up a request are reported as transport errors.
+pub trait AsyncTransport {
+    fn send<R: FromResponseParts + Send + 'static>(
+        &self,
+        request: Request,
+        base_url: &str,
+    ) -> ResponseFuture<R>;
+
+    /// Sends the request of a call of the given kind, see `Transport::send_call`.
+    fn send_call<R: FromResponseParts + Send + 'static>(
+        &self,
+        _kind: Kind,
+        request: Request,
+        base_url: &str,
+    ) -> ResponseFuture<R> {
+        self.send(request, base_url)
+    }
+}
+
+/// The configuration shared by `Client` and `AsyncClient`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Config {
+    base_url: String,
+    service: Service,
+    extensions: Option<List<'static>>,
+    endpoints: Endpoints,
+}
+
+impl Config {
+    fn new(base_url: String, service: Service) -> Self {
+        Self {
+            // paths always start with a slash
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            service,
+            extensions: None,
+            endpoints: Endpoints::new(),
+        }
+    }
+
+    fn request(
+        &self,
+        kind: Kind,
+        oauth: bool,
+        transactions: &[Transaction],
+    ) -> Result<Request, Error> {
+        // transactions are invariant over their lifetime, which can outlive the borrow of self,
+        // so the call owns its data rather than borrowing both
+        let apicall = ApiCall::new_owned(
+            kind,
+            self.service.clone(),
+            transactions.to_vec(),
+            self.extensions.clone(),
+        )?;
+
+        Ok(if oauth {
+            Request::from_api_call_to(&apicall, &self.endpoints, Endpoint::new(kind, true))
+        } else {
+            Request::from_api_call(&apicall, &self.endpoints)
+        })
+    }
+}
+
+macro_rules! config_methods {
+    () => {
+        /// Sets the extensions sent with every call.
+        pub fn with_extensions(mut self, extensions: List<'_>) -> Self {
+            self.config.extensions = Some(extensions.into_owned());
+            self
+        }
+
+        /// Sets the paths used for each endpoint.
+        pub fn with_endpoints(mut self, endpoints: Endpoints) -> Self {
+            self.config.endpoints = endpoints;
+            self
+        }
+
+        pub fn base_url(&self) -> &str {
+            self.config.base_url.as_str()
+        }
+
+        pub fn service(&self) -> &Service {
+            &self.config.service
+        }
+
+        pub fn extensions(&self) -> Option<&List<'static>> {
+            self.config.extensions.as_ref()
+        }
+
+        pub fn endpoints(&self) -> &Endpoints {
+            &self.config.endpoints
+        }
+
+        pub fn transport(&self) -> &T {
+            &self.transport
+        }
+
+        pub fn into_transport(self) -> T {
+            self.transport
+        }
+    };
+}
+
+/// A client sending the calls of a service through a blocking `Transport`.
+///
+/// The endpoint used for authorizations and authreps depends on the credentials, as with
+/// `Request::from_api_call`, but can be forced to be the OAuth one with the `oauth_*` methods.
+#[derive(Debug, Clone)]
+pub struct Client<T> {
+    transport: T,
+    config: Config,
+}
+
+impl<T> Client<T> {
+    pub fn new<U: Into<String>>(transport: T, base_url: U, service: Service) -> Self {
+        Self {
+            transport,
+            config: Config::new(base_url.into(), service),
+        }
+    }
+
+    config_methods!();
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+}
+
+impl<T: Transport> Client<T> {
+    pub fn authorize(&mut self, txn: &Transaction) -> Result<AuthorizationResponse, ResponseError> {
+        self.send(Kind::Authorize, false, core::slice::from_ref(txn))
+    }
+
+    pub fn authrep(&mut self, txn: &Transaction) -> Result<AuthorizationResponse, ResponseError> {
+        self.send(Kind::AuthRep, false, core::slice::from_ref(txn))
+    }
+
+    /// Reports the transactions, failing with a transport error without sending anything if there
+    /// are none.
+    pub fn report(&mut self, txns: &[Transaction]) -> Result<ReportResponse, ResponseError> {
+        self.send(Kind::Report, false, txns)
+    }
+
+    pub fn oauth_authorize(
+        &mut self,
+        txn: &Transaction,
+    ) -> Result<AuthorizationResponse, ResponseError> {
+        self.send(Kind::Authorize, true, core::slice::from_ref(txn))
+    }
+
+    pub fn oauth_authrep(
+        &mut self,
+        txn: &Transaction,
+    ) -> Result<AuthorizationResponse, ResponseError> {
+        self.send(Kind::AuthRep, true, core::slice::from_ref(txn))
+    }
+
+    fn send<R: FromResponseParts>(
+        &mut self,
+        kind: Kind,
+        oauth: bool,
+        txns: &[Transaction],
+    ) -> Result<R, ResponseError> {
+        let request = self
+            .config
+            .request(kind, oauth, txns)
+            .map_err(ResponseError::Transport)?;
+
+        self.transport
+            .send_call(kind, request, self.config.base_url.as_str())
+    }
+}
+
+/// A client sending the calls of a service through an `AsyncTransport`.
+///
+/// Requests are built before returning the futures, so these don't borrow the transactions.
+#[derive(Debug, Clone)]
+pub struct AsyncClient<T> {
+    transport: T,
+    config: Config,
+}
+
+impl<T> AsyncClient<T> {
+    pub fn new<U: Into<String>>(transport: T, base_url: U, service: Service) -> Self {
+        Self {
+            transport,
+            config: Config::new(base_url.into(), service),
+        }
+    }
+
+    config_methods!();
+}
+
+impl<T: AsyncTransport> AsyncClient<T> {
+    pub fn authorize(&self, txn: &Transaction) -> ResponseFuture<AuthorizationResponse> {
+        self.send(Kind::Authorize, false, core::slice::from_ref(txn))
+    }
+
+    pub fn authrep(&self, txn: &Transaction) -> ResponseFuture<AuthorizationResponse> {
+        self.send(Kind::AuthRep, false, core::slice::from_ref(txn))
+    }
+
+    /// Reports the transactions, failing with a transport error without sending anything if there
+    /// are none.
+    pub fn report(&self, txns: &[Transaction]) -> ResponseFuture<ReportResponse> {
+        self.send(Kind::Report, false, txns)
+    }
+
+    pub fn oauth_authorize(&self, txn: &Transaction) -> ResponseFuture<AuthorizationResponse> {
+        self.send(Kind::Authorize, true, core::slice::from_ref(txn))
+    }
+
+    pub fn oauth_authrep(&self, txn: &Transaction) -> ResponseFuture<AuthorizationResponse> {
+        self.send(Kind::AuthRep, true, core::slice::from_ref(txn))
+    }
+
+    fn send<R: FromResponseParts + Send + 'static>(
+        &self,
+        kind: Kind,
+        oauth: bool,
+        txns: &[Transaction],
+    ) -> ResponseFuture<R> {
+        let request = match self.config.request(kind, oauth, txns) {
+            Ok(request) => request,
+            Err(e) => return Box::pin(async move { Err(ResponseError::Transport(e)) }),
+        };
+
+        self.transport
+            .send_call(kind, request, self.config.base_url.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        anyhow,
+        extensions::{self, Extension},
+        fixtures::{service, transaction},
+        http::{HeaderMap, Method},
+    };
+    use core::{
+        future::Future,
+        task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    };
+
+    // records requests and replies with a fixed response
+    #[derive(Debug, Default)]
+    struct Recorder {
+        requests: Vec<(String, Request)>,
+        status: u16,
+        body: &'static str,
+    }
+
+    impl Recorder {
+        fn new(status: u16, body: &'static str) -> Self {
+            Self {
+                requests: Vec::new(),
+                status,
+                body,
+            }
+        }
+
+        fn reply<R: FromResponseParts>(&self) -> Result<R, ResponseError> {
+            R::from_response_parts(self.status, &HeaderMap::new(), Some(self.body))
+                .map_err(ResponseError::Protocol)
+        }
+    }
+
+    impl Transport for Recorder {
+        fn send<R: FromResponseParts>(
+            &mut self,
+            request: Request,
+            base_url: &str,
+        ) -> Result<R, ResponseError> {
+            self.requests.push((base_url.to_owned(), request));
+            self.reply()
+        }
+    }
+
+    struct Unreachable;
+
+    impl AsyncTransport for Unreachable {
+        fn send<R: FromResponseParts + Send + 'static>(
+            &self,
+            request: Request,
+            _base_url: &str,
+        ) -> ResponseFuture<R> {
+            Box::pin(async move {
+                Err(ResponseError::Transport(anyhow!(
+                    "could not connect to send {}",
+                    request.path
+                )))
+            })
+        }
+    }
+
+    // the futures in these tests complete without being woken up
+    pub(super) fn block_on<F: Future>(future: F) -> F::Output {
+        fn raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                raw_waker()
+            }
+            fn noop(_: *const ()) {}
+
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(core::ptr::null(), &VTABLE)
+        }
+
+        let waker = unsafe { Waker::from_raw(raw_waker()) };
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn sends_calls_with_defaults() {
+        let transport = Recorder::new(
+            200,
+            "<status><authorized>true</authorized><plan>Basic</plan></status>",
+        );
+        let extensions = extensions::List::new().push(Extension::Hierarchy);
+        let mut client = Client::new(transport, "https://su1.3scale.net/", service())
+            .with_extensions(extensions);
+        let txn = transaction();
+
+        assert!(client.authorize(&txn).unwrap().is_authorized());
+        assert!(client.oauth_authrep(&txn).unwrap().is_authorized());
+
+        let requests = &client.transport().requests;
+        assert_eq!(requests[0].0, "https://su1.3scale.net");
+        assert_eq!(requests[0].1.path, "/transactions/authorize.xml");
+        assert_eq!(
+            requests[0].1.headers.get("3scale-options"),
+            Some("hierarchy=1")
+        );
+        assert_eq!(requests[1].1.path, "/transactions/oauth_authrep.xml");
+    }
+
+    #[test]
+    fn sends_reports() {
+        let transport = Recorder::new(202, "");
+        let endpoints = Endpoints::new().with_base_path("/backend");
+        let mut client =
+            Client::new(transport, "https://su1.3scale.net", service()).with_endpoints(endpoints);
+        let txns = [transaction()];
+
+        assert!(client.report(&txns).unwrap().is_accepted());
+
+        let (_, request) = &client.transport().requests[0];
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.path, "/backend/transactions.xml");
+    }
+
+    #[test]
+    fn refuses_empty_reports() {
+        let mut client = Client::new(Recorder::new(202, ""), "https://su1.3scale.net", service());
+
+        assert!(client.report(&[]).unwrap_err().is_transport());
+        assert!(client.transport().requests.is_empty());
+
+        let client = AsyncClient::new(Unreachable, "https://su1.3scale.net", service());
+        let error = block_on(client.report(&[])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "transport error: a report call requires at least one transaction"
+        );
+    }
+
+    #[test]
+    fn surfaces_protocol_errors() {
+        let transport = Recorder::new(200, "<html>");
+        let mut client = Client::new(transport, "https://su1.3scale.net", service());
+        let txn = transaction();
+
+        assert!(client.authrep(&txn).unwrap_err().is_protocol());
+    }
+
+    #[test]
+    fn sends_async_calls() {
+        let client = AsyncClient::new(Unreachable, "https://su1.3scale.net", service());
+        let future = client.oauth_authorize(&transaction());
+
+        let error = block_on(future).unwrap_err();
+        assert!(error.is_transport());
+        assert_eq!(
+            error.to_string(),
+            "transport error: could not connect to send /transactions/oauth_authorize.xml"
+        );
+    }
+}
@@ -0,0 +1,808 @@
+//! Retries of failed calls with exponential backoff.
+//!
+//! Whether a call can be retried safely depends on its kind: authorizations are idempotent, but
+//! authreps and reports increment usage counters, so sending them again after a failure that
+//! might have happened once Apisonator got the request could count the usage twice. For this
+//! reason `RetryPolicy` refuses to retry non-idempotent calls unless the caller opts in, and even
+//! then only retries them after failing to connect, when the request never reached Apisonator.
+//!
+//! A `Retry` wraps a transport to retry the calls sent through it according to the policy for
+//! each kind of call, waiting for the backoff delays using a `Clock`.
+//!
+//! # Examples
+//!
+//! ```
+//! use core::time::Duration;
+//! use threescalers::{
+//!     api_call::Kind,
+//!     client::{
+//!         retry::{Backoff, Clock, Retry, RetryPolicy},
+//!         Transport,
+//!     },
+//! };
+//!
+//! # fn with_transport<T: Transport, C: Clock>(transport: T, clock: C) {
+//! let backoff = Backoff::new(Duration::from_millis(50), Duration::from_secs(2));
+//! let policy = RetryPolicy::new(3).with_backoff(backoff);
+//!
+//! // retry authorizations up to 3 times, and reports once even though they are not idempotent
+//! let transport = Retry::new(transport, clock)
+//!     .with_policy(Kind::Authorize, policy.clone())
+//!     .with_policy(Kind::Report, policy.with_max_retries(1).with_non_idempotent(true));
+//! # }
+//! ```
+use std::prelude::v1::*;
+
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use super::{AsyncTransport, Transport};
+use crate::{
+    api_call::Kind,
+    http::{
+        response::{FromResponseParts, ResponseError, ResponseFuture},
+        Request,
+    },
+};
+
+/// Exponential backoff, with the delay before each retry being the initial delay multiplied by
+/// the multiplier once per previous retry, up to a maximum delay.
+///
+/// With jitter the actual delay is randomly picked between half the delay and the delay, so that
+/// clients failing at the same time don't retry at the same time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    multiplier: u32,
+    jitter: bool,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            multiplier: 2,
+            jitter: true,
+        }
+    }
+
+    pub fn with_multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn initial(&self) -> Duration {
+        self.initial
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        self.multiplier
+    }
+
+    pub fn jitter(&self) -> bool {
+        self.jitter
+    }
+
+    /// The delay before the given retry, starting from 0, without jitter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use threescalers::client::retry::Backoff;
+    ///
+    /// let backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(300));
+    ///
+    /// assert_eq!(backoff.delay(0), Duration::from_millis(100));
+    /// assert_eq!(backoff.delay(1), Duration::from_millis(200));
+    /// assert_eq!(backoff.delay(2), Duration::from_millis(300));
+    /// ```
+    pub fn delay(&self, retry: u32) -> Duration {
+        let mut delay = self.initial.min(self.max);
+
+        for _ in 0..retry {
+            if delay >= self.max {
+                break;
+            }
+            delay = delay
+                .checked_mul(self.multiplier)
+                .map_or(self.max, |d| d.min(self.max));
+        }
+
+        delay
+    }
+
+    fn jittered_delay(&self, retry: u32, rng: &mut Rng) -> Duration {
+        let delay = self.delay(retry);
+
+        if !self.jitter {
+            return delay;
+        }
+
+        // equal jitter: keep half of the delay and randomize the other half
+        let half = delay / 2;
+        let range = (delay - half).as_nanos() as u64;
+        let extra = match range.checked_add(1) {
+            Some(range) => rng.next() % range,
+            None => rng.next(),
+        };
+
+        half + Duration::from_nanos(extra)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(100), Duration::from_secs(5))
+    }
+}
+
+/// Determines which failed calls are retried, how many times and how long to wait in between.
+///
+/// By default transport errors, such as failures to connect, and 5xx responses are retried, and
+/// non-idempotent calls are never retried. When allowed, non-idempotent calls are only retried
+/// after failing to connect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    backoff: Backoff,
+    transport_errors: bool,
+    server_errors: bool,
+    non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            backoff: Backoff::default(),
+            transport_errors: true,
+            server_errors: true,
+            non_idempotent: false,
+        }
+    }
+
+    /// A policy that never retries.
+    pub fn never() -> Self {
+        Self::new(0)
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets whether errors sending requests or receiving responses are retried.
+    pub fn with_transport_errors(mut self, retry: bool) -> Self {
+        self.transport_errors = retry;
+        self
+    }
+
+    /// Sets whether responses with 5xx status codes are retried.
+    pub fn with_server_errors(mut self, retry: bool) -> Self {
+        self.server_errors = retry;
+        self
+    }
+
+    /// Allows retrying calls that are not idempotent after failing to connect to Apisonator.
+    ///
+    /// Other failures are not retried for these calls, since Apisonator could have processed a
+    /// call whose response was lost and sending it again would count its usage more than once.
+    pub fn with_non_idempotent(mut self, retry: bool) -> Self {
+        self.non_idempotent = retry;
+        self
+    }
+
+    pub fn backoff(&self) -> &Backoff {
+        &self.backoff
+    }
+
+    pub fn non_idempotent(&self) -> bool {
+        self.non_idempotent
+    }
+
+    /// The maximum number of retries for a call of the given kind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::{api_call::Kind, client::retry::RetryPolicy};
+    ///
+    /// let policy = RetryPolicy::new(3);
+    ///
+    /// assert_eq!(policy.max_retries(Kind::Authorize), 3);
+    /// assert_eq!(policy.max_retries(Kind::AuthRep), 0);
+    /// assert_eq!(policy.with_non_idempotent(true).max_retries(Kind::AuthRep), 3);
+    /// ```
+    pub fn max_retries(&self, kind: Kind) -> u32 {
+        if kind.is_idempotent() || self.non_idempotent {
+            self.max_retries
+        } else {
+            0
+        }
+    }
+
+    /// Whether the result of an attempt of a call of the given kind is a failure that this policy
+    /// retries.
+    pub fn should_retry<R: FromResponseParts>(
+        &self,
+        kind: Kind,
+        result: &Result<R, ResponseError>,
+    ) -> bool {
+        if !kind.is_idempotent() {
+            return self.non_idempotent && self.transport_errors && is_connect_error(result);
+        }
+
+        match result {
+            Ok(response) => self.server_errors && is_server_error(response.status_code()),
+            Err(e) if e.is_connect() || e.is_transport() => self.transport_errors,
+            Err(e) if e.is_server() => self.server_errors,
+            Err(_) => false,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+fn is_server_error(status: u16) -> bool {
+    (500..600).contains(&status)
+}
+
+fn is_connect_error<R>(result: &Result<R, ResponseError>) -> bool {
+    match result {
+        Err(e) => e.is_connect(),
+        Ok(_) => false,
+    }
+}
+
+/// The retry policy for each kind of call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetryPolicies {
+    authorize: RetryPolicy,
+    authrep: RetryPolicy,
+    report: RetryPolicy,
+}
+
+impl RetryPolicies {
+    /// Uses the same policy for all kinds of calls.
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            authorize: policy.clone(),
+            authrep: policy.clone(),
+            report: policy,
+        }
+    }
+
+    pub fn with_policy(mut self, kind: Kind, policy: RetryPolicy) -> Self {
+        *self.policy_mut(kind) = policy;
+        self
+    }
+
+    pub fn policy(&self, kind: Kind) -> &RetryPolicy {
+        match kind {
+            Kind::Authorize => &self.authorize,
+            Kind::AuthRep => &self.authrep,
+            Kind::Report => &self.report,
+        }
+    }
+
+    pub fn policy_mut(&mut self, kind: Kind) -> &mut RetryPolicy {
+        match kind {
+            Kind::Authorize => &mut self.authorize,
+            Kind::AuthRep => &mut self.authrep,
+            Kind::Report => &mut self.report,
+        }
+    }
+}
+
+/// Waits for the backoff delays of blocking transports.
+pub trait Clock {
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// A clock sleeping the current thread.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadClock;
+
+#[cfg(feature = "std")]
+impl Clock for ThreadClock {
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// Waits for the backoff delays of asynchronous transports, ie. using the runtime's timers.
+pub trait AsyncClock {
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+// splitmix64, good enough to spread retries out
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(feature = "std")]
+fn random_seed() -> u64 {
+    use std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+    };
+
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(not(feature = "std"))]
+fn random_seed() -> u64 {
+    0x5eed_5eed_5eed_5eed
+}
+
+/// A transport retrying the calls sent through it according to the policy for their kind.
+///
+/// Requests sent without a kind, using `send`, are not retried.
+///
+/// The jitter of the delays is seeded randomly when using std, and with a fixed value otherwise,
+/// in which case `with_seed` should be used to avoid retrying in lockstep with other clients.
+#[derive(Debug)]
+pub struct Retry<T, C> {
+    transport: T,
+    clock: C,
+    policies: RetryPolicies,
+    seed: u64,
+    calls: AtomicUsize,
+}
+
+impl<T, C> Retry<T, C> {
+    pub fn new(transport: T, clock: C) -> Self {
+        Self {
+            transport,
+            clock,
+            policies: RetryPolicies::default(),
+            seed: random_seed(),
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn with_policies(mut self, policies: RetryPolicies) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    pub fn with_policy(mut self, kind: Kind, policy: RetryPolicy) -> Self {
+        self.policies = self.policies.with_policy(kind, policy);
+        self
+    }
+
+    /// Sets the seed used to compute the jitter of the delays.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn policies(&self) -> &RetryPolicies {
+        &self.policies
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn into_inner(self) -> (T, C) {
+        (self.transport, self.clock)
+    }
+
+    // each call gets its own sequence of random numbers
+    fn rng(&self) -> Rng {
+        let call = self.calls.fetch_add(1, Ordering::Relaxed) as u64;
+
+        Rng(self
+            .seed
+            .wrapping_add(call.wrapping_mul(0xd6e8_feb8_6659_fd93)))
+    }
+}
+
+impl<T: Transport, C: Clock> Transport for Retry<T, C> {
+    fn send<R: FromResponseParts>(
+        &mut self,
+        request: Request,
+        base_url: &str,
+    ) -> Result<R, ResponseError> {
+        self.transport.send(request, base_url)
+    }
+
+    fn send_call<R: FromResponseParts>(
+        &mut self,
+        kind: Kind,
+        request: Request,
+        base_url: &str,
+    ) -> Result<R, ResponseError> {
+        let mut rng = self.rng();
+        let policy = self.policies.policy(kind);
+
+        for retry in 0..policy.max_retries(kind) {
+            let result = self.transport.send_call(kind, request.clone(), base_url);
+            if !policy.should_retry(kind, &result) {
+                return result;
+            }
+            self.clock
+                .sleep(policy.backoff().jittered_delay(retry, &mut rng));
+        }
+
+        self.transport.send_call(kind, request, base_url)
+    }
+}
+
+impl<T, C> AsyncTransport for Retry<T, C>
+where
+    T: AsyncTransport + Clone + Send + Sync + 'static,
+    C: AsyncClock + Clone + Send + Sync + 'static,
+{
+    fn send<R: FromResponseParts + Send + 'static>(
+        &self,
+        request: Request,
+        base_url: &str,
+    ) -> ResponseFuture<R> {
+        self.transport.send(request, base_url)
+    }
+
+    fn send_call<R: FromResponseParts + Send + 'static>(
+        &self,
+        kind: Kind,
+        request: Request,
+        base_url: &str,
+    ) -> ResponseFuture<R> {
+        let mut rng = self.rng();
+        let policy = self.policies.policy(kind).clone();
+        let transport = self.transport.clone();
+        let clock = self.clock.clone();
+        let base_url = base_url.to_owned();
+
+        Box::pin(async move {
+            for retry in 0..policy.max_retries(kind) {
+                let result = transport
+                    .send_call(kind, request.clone(), base_url.as_str())
+                    .await;
+                if !policy.should_retry(kind, &result) {
+                    return result;
+                }
+                clock
+                    .sleep(policy.backoff().jittered_delay(retry, &mut rng))
+                    .await;
+            }
+
+            transport.send_call(kind, request, base_url.as_str()).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        anyhow,
+        client::{tests::block_on, AsyncClient, Client},
+        fixtures::{service, transaction},
+        http::HeaderMap,
+    };
+    use std::sync::Arc;
+
+    const AUTHORIZED: &str = "<status><authorized>true</authorized><plan>Basic</plan></status>";
+
+    #[derive(Debug, Clone, Copy)]
+    enum Reply {
+        Connect,
+        Reset,
+        Status(u16, &'static str),
+    }
+
+    // a simulated transport replying to each attempt in turn, repeating the last reply
+    #[derive(Debug, Clone)]
+    struct Scripted {
+        replies: Arc<Vec<Reply>>,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    impl Scripted {
+        fn new(replies: Vec<Reply>) -> Self {
+            Self {
+                replies: Arc::new(replies),
+                attempts: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn attempts(&self) -> usize {
+            self.attempts.load(Ordering::SeqCst)
+        }
+
+        fn reply<R: FromResponseParts>(&self) -> Result<R, ResponseError> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+            let reply = self.replies[attempt.min(self.replies.len() - 1)];
+
+            match reply {
+                Reply::Connect => Err(ResponseError::Connect(anyhow!("connection refused"))),
+                Reply::Reset => Err(ResponseError::Transport(anyhow!("connection reset"))),
+                Reply::Status(status, body) => crate::http::response::typed_response(
+                    status,
+                    Ok(HeaderMap::new()),
+                    body.as_bytes(),
+                ),
+            }
+        }
+    }
+
+    impl Transport for Scripted {
+        fn send<R: FromResponseParts>(
+            &mut self,
+            _request: Request,
+            _base_url: &str,
+        ) -> Result<R, ResponseError> {
+            self.reply()
+        }
+    }
+
+    impl AsyncTransport for Scripted {
+        fn send<R: FromResponseParts + Send + 'static>(
+            &self,
+            _request: Request,
+            _base_url: &str,
+        ) -> ResponseFuture<R> {
+            let result = self.reply();
+
+            Box::pin(async move { result })
+        }
+    }
+
+    // a simulated clock that just keeps track of the time slept
+    #[derive(Debug, Clone, Default)]
+    struct SimClock {
+        sleeps: Arc<AtomicUsize>,
+        elapsed_ms: Arc<AtomicUsize>,
+    }
+
+    impl SimClock {
+        fn sleeps(&self) -> usize {
+            self.sleeps.load(Ordering::SeqCst)
+        }
+
+        fn elapsed(&self) -> Duration {
+            Duration::from_millis(self.elapsed_ms.load(Ordering::SeqCst) as u64)
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.sleeps.fetch_add(1, Ordering::SeqCst);
+            self.elapsed_ms
+                .fetch_add(duration.as_millis() as usize, Ordering::SeqCst);
+        }
+    }
+
+    impl Clock for SimClock {
+        fn sleep(&mut self, duration: Duration) {
+            self.advance(duration)
+        }
+    }
+
+    impl AsyncClock for SimClock {
+        fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            self.advance(duration);
+
+            Box::pin(async {})
+        }
+    }
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        let backoff =
+            Backoff::new(Duration::from_millis(100), Duration::from_secs(1)).with_jitter(false);
+
+        RetryPolicy::new(max_retries).with_backoff(backoff)
+    }
+
+    fn client(
+        replies: Vec<Reply>,
+        policies: RetryPolicies,
+    ) -> (Client<Retry<Scripted, SimClock>>, Scripted, SimClock) {
+        let transport = Scripted::new(replies);
+        let clock = SimClock::default();
+        let retry = Retry::new(transport.clone(), clock.clone()).with_policies(policies);
+
+        (
+            Client::new(retry, "https://su1.3scale.net", service()),
+            transport,
+            clock,
+        )
+    }
+
+    #[test]
+    fn computes_exponential_delays() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1))
+            .with_multiplier(3)
+            .with_jitter(false);
+        let delays = (0..4).map(|retry| backoff.delay(retry)).collect::<Vec<_>>();
+
+        assert_eq!(
+            delays,
+            [100, 300, 900, 1000]
+                .iter()
+                .map(|&ms| Duration::from_millis(ms))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(backoff.delay(1_000), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn jitters_delays_within_bounds() {
+        let backoff = Backoff::default();
+        let mut rng = Rng(42);
+        let delays = (0..64)
+            .map(|_| backoff.jittered_delay(3, &mut rng))
+            .collect::<Vec<_>>();
+
+        assert!(delays
+            .iter()
+            .all(|&d| d >= Duration::from_millis(400) && d <= Duration::from_millis(800)));
+        assert!(delays.iter().any(|&d| d != delays[0]));
+
+        // the same seed results in the same delays
+        let mut rng = Rng(42);
+        assert_eq!(backoff.jittered_delay(3, &mut rng), delays[0]);
+    }
+
+    #[test]
+    fn retries_idempotent_calls() {
+        let replies = vec![
+            Reply::Connect,
+            Reply::Status(503, "<html>"),
+            Reply::Status(200, AUTHORIZED),
+        ];
+        let (mut client, transport, clock) = client(replies, RetryPolicies::new(policy(3)));
+        let txn = transaction();
+
+        assert!(client.authorize(&txn).unwrap().is_authorized());
+        assert_eq!(transport.attempts(), 3);
+        assert_eq!(clock.sleeps(), 2);
+        assert_eq!(clock.elapsed(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let (mut client, transport, clock) =
+            client(vec![Reply::Connect], RetryPolicies::new(policy(2)));
+        let txn = transaction();
+
+        assert!(client.authorize(&txn).unwrap_err().is_connect());
+        assert_eq!(transport.attempts(), 3);
+        assert_eq!(clock.elapsed(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn refuses_to_retry_non_idempotent_calls() {
+        let replies = vec![Reply::Connect, Reply::Status(202, "")];
+        let (mut client, transport, clock) = client(replies, RetryPolicies::new(policy(3)));
+        let txn = transaction();
+
+        assert!(client.authrep(&txn).unwrap_err().is_connect());
+        assert!(client.report(&[txn]).unwrap().is_accepted());
+        assert_eq!(transport.attempts(), 2);
+        assert_eq!(clock.sleeps(), 0);
+    }
+
+    #[test]
+    fn retries_non_idempotent_calls_when_allowed() {
+        let replies = vec![Reply::Connect, Reply::Status(202, "")];
+        let policies = RetryPolicies::new(policy(3))
+            .with_policy(Kind::Report, policy(1).with_non_idempotent(true));
+        let (mut client, transport, clock) = client(replies, policies);
+        let txn = transaction();
+
+        assert!(client.report(&[txn]).unwrap().is_accepted());
+        assert_eq!(transport.attempts(), 2);
+        assert_eq!(clock.sleeps(), 1);
+    }
+
+    #[test]
+    fn retries_non_idempotent_calls_only_after_failing_to_connect() {
+        let replies = vec![
+            Reply::Reset,
+            Reply::Status(503, "<html>"),
+            Reply::Status(202, ""),
+        ];
+        let policies = RetryPolicies::new(policy(3).with_non_idempotent(true));
+        let (mut reporter, transport, clock) = client(replies, policies);
+        let txn = transaction();
+
+        // the request could have reached Apisonator before the connection was reset
+        assert!(reporter.report(&[txn.clone()]).unwrap_err().is_transport());
+        assert_eq!(reporter.authrep(&txn).unwrap_err().status(), Some(503));
+        assert_eq!(transport.attempts(), 2);
+        assert_eq!(clock.sleeps(), 0);
+
+        // while authorizations are retried after any transport error
+        let replies = vec![Reply::Reset, Reply::Status(200, AUTHORIZED)];
+        let (mut client, transport, _) = client(replies, RetryPolicies::new(policy(3)));
+
+        assert!(client.authorize(&txn).unwrap().is_authorized());
+        assert_eq!(transport.attempts(), 2);
+    }
+
+    #[test]
+    fn does_not_retry_client_or_protocol_errors() {
+        let replies = vec![
+            Reply::Status(403, "<error code=\"user_key_invalid\">invalid</error>"),
+            Reply::Status(200, "<html>"),
+            Reply::Status(200, AUTHORIZED),
+        ];
+        let (mut client, transport, _) = client(replies, RetryPolicies::new(policy(3)));
+        let txn = transaction();
+
+        assert!(!client.authorize(&txn).unwrap().is_authorized());
+        assert!(client.authorize(&txn).unwrap_err().is_protocol());
+        assert_eq!(transport.attempts(), 2);
+    }
+
+    #[test]
+    fn retries_only_the_errors_enabled() {
+        let replies = vec![Reply::Status(503, "<html>"), Reply::Status(200, AUTHORIZED)];
+        let policies = RetryPolicies::new(policy(3).with_server_errors(false));
+        let (mut client, transport, _) = client(replies, policies);
+        let txn = transaction();
+
+        let error = client.authorize(&txn).unwrap_err();
+        assert_eq!(error.status(), Some(503));
+        assert_eq!(transport.attempts(), 1);
+    }
+
+    #[test]
+    fn retries_async_calls() {
+        let transport = Scripted::new(vec![Reply::Connect, Reply::Status(200, AUTHORIZED)]);
+        let clock = SimClock::default();
+        let retry = Retry::new(transport.clone(), clock.clone())
+            .with_policy(Kind::Authorize, policy(1))
+            .with_seed(42);
+        let client = AsyncClient::new(retry, "https://su1.3scale.net", service());
+        let future = client.authorize(&transaction());
+
+        assert!(block_on(future).unwrap().is_authorized());
+        assert_eq!(transport.attempts(), 2);
+        assert_eq!(clock.elapsed(), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,184 @@
+// Transport implementations for the supported clients.
+
+#[cfg(any(
+    feature = "reqwest-sync",
+    feature = "reqwest-async",
+    feature = "curl-easy2",
+    feature = "hyper"
+))]
+fn send_error(connect: bool, e: impl core::fmt::Display) -> crate::http::response::ResponseError {
+    use crate::{anyhow, http::response::ResponseError};
+
+    let error = anyhow!("failed to send request: {}", e);
+
+    // only failures to connect are known not to have reached Apisonator
+    if connect {
+        ResponseError::Connect(error)
+    } else {
+        ResponseError::Transport(error)
+    }
+}
+
+#[cfg(feature = "reqwest-sync")]
+mod reqwest_sync {
+    use super::send_error;
+    use crate::{
+        client::Transport,
+        http::{
+            request::SetupRequest,
+            response::{FromResponseParts, ReadResponse, ResponseError},
+            Request,
+        },
+    };
+
+    impl Transport for reqwest::blocking::Client {
+        fn send<R: FromResponseParts>(
+            &mut self,
+            request: Request,
+            base_url: &str,
+        ) -> Result<R, ResponseError> {
+            self.setup_request(request, base_url)
+                .map_err(ResponseError::Transport)?
+                .send()
+                .map_err(|e| send_error(e.is_connect(), e))?
+                .read_response(())
+        }
+    }
+}
+
+#[cfg(feature = "reqwest-async")]
+mod reqwest_async {
+    use std::prelude::v1::*;
+
+    use super::send_error;
+    use crate::{
+        client::AsyncTransport,
+        http::{
+            request::SetupRequest,
+            response::{FromResponseParts, ReadResponse, ResponseError, ResponseFuture},
+            Request,
+        },
+    };
+
+    impl AsyncTransport for reqwest::Client {
+        fn send<R: FromResponseParts + Send + 'static>(
+            &self,
+            request: Request,
+            base_url: &str,
+        ) -> ResponseFuture<R> {
+            // reqwest's clients are reference counted, so cloning them is cheap
+            let request = self.clone().setup_request(request, base_url);
+
+            Box::pin(async move {
+                request
+                    .map_err(ResponseError::Transport)?
+                    .send()
+                    .await
+                    .map_err(|e| send_error(e.is_connect(), e))?
+                    .read_response(())
+                    .await
+            })
+        }
+    }
+}
+
+#[cfg(feature = "curl-easy2")]
+mod curl {
+    use super::send_error;
+    use crate::{
+        client::Transport,
+        http::{
+            request::{
+                curl::{CollectResponse, SetBody},
+                SetupRequest,
+            },
+            response::{FromResponseParts, ReadResponse, ResponseError},
+            Request,
+        },
+    };
+
+    impl<H: SetBody + CollectResponse> Transport for curl::easy::Easy2<H> {
+        fn send<R: FromResponseParts>(
+            &mut self,
+            request: Request,
+            base_url: &str,
+        ) -> Result<R, ResponseError> {
+            self.setup_request(request, base_url)
+                .map_err(ResponseError::Transport)?;
+            self.perform().map_err(|e| {
+                let connect = e.is_couldnt_connect()
+                    || e.is_couldnt_resolve_host()
+                    || e.is_couldnt_resolve_proxy();
+
+                send_error(connect, e)
+            })?;
+
+            (&*self).read_response(())
+        }
+    }
+}
+
+#[cfg(feature = "ureq")]
+mod ureq {
+    use crate::{
+        client::Transport,
+        http::{
+            request::SetupRequest,
+            response::{FromResponseParts, ReadResponse, ResponseError},
+            Request,
+        },
+    };
+
+    impl Transport for ureq::Agent {
+        fn send<R: FromResponseParts>(
+            &mut self,
+            request: Request,
+            base_url: &str,
+        ) -> Result<R, ResponseError> {
+            self.setup_request(request, base_url)
+                .map_err(ResponseError::Transport)?
+                .call()
+                .read_response(())
+        }
+    }
+}
+
+#[cfg(feature = "hyper")]
+mod hyper {
+    use std::prelude::v1::*;
+
+    use super::send_error;
+    use crate::{
+        client::AsyncTransport,
+        http::{
+            request::SetupRequest,
+            response::{FromResponseParts, ReadResponse, ResponseError, ResponseFuture},
+            Request,
+        },
+    };
+
+    impl<C> AsyncTransport for hyper_crate::Client<C, hyper_crate::Body>
+    where
+        C: hyper_crate::client::connect::Connect + Clone + Send + Sync + 'static,
+    {
+        fn send<R: FromResponseParts + Send + 'static>(
+            &self,
+            request: Request,
+            base_url: &str,
+        ) -> ResponseFuture<R> {
+            let request = match hyper_crate::Request::builder().setup_request(request, base_url) {
+                Ok(request) => request,
+                Err(e) => return Box::pin(async move { Err(ResponseError::Transport(e)) }),
+            };
+            let response = self.request(request);
+
+            Box::pin(async move {
+                response
+                    .await
+                    .map_err(|e| send_error(e.is_connect(), e))?
+                    .read_response(())
+                    .await
+            })
+        }
+    }
+}
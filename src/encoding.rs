@@ -1,4 +1,7 @@
-use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use std::prelude::v1::*;
+
+use crate::{anyhow, Error};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
 use std::borrow::Cow;
 
 const QUERY_ENCODE_SET: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'<').add(b'>');
@@ -10,7 +13,54 @@ const APISONATOR_EXTENSION_ENCODE_SET: &AsciiSet = &PATH_SEGMENT_ENCODE_SET
     .add(b'=')
     .add(b'[')
     .add(b']');
+// Only unreserved characters are left alone in parameters, so that form decoders don't mistake
+// any of them as separators or as an encoded space ('+'). Keys keep brackets, as they are used by
+// the Rack format to express nesting, ie. transactions[0][usage][hits].
+const PARAMETER_KEY_ENCODE_SET: &AsciiSet = &PATH_SEGMENT_ENCODE_SET
+    .add(b'!')
+    .add(b'$')
+    .add(b'&')
+    .add(b'\'')
+    .add(b'(')
+    .add(b')')
+    .add(b'*')
+    .add(b'+')
+    .add(b',')
+    .add(b':')
+    .add(b';')
+    .add(b'=')
+    .add(b'@')
+    .add(b'\\')
+    .add(b'^')
+    .add(b'|');
+const PARAMETER_VALUE_ENCODE_SET: &AsciiSet = &PARAMETER_KEY_ENCODE_SET.add(b'[').add(b']');
 
 pub fn encode(s: &str) -> Cow<str> {
     utf8_percent_encode(s, APISONATOR_EXTENSION_ENCODE_SET).into()
 }
+
+/// Encodes a parameter key, leaving the brackets used for nesting untouched.
+pub fn encode_key(s: &str) -> Cow<str> {
+    utf8_percent_encode(s, PARAMETER_KEY_ENCODE_SET).into()
+}
+
+/// Encodes a parameter value.
+pub fn encode_value(s: &str) -> Cow<str> {
+    utf8_percent_encode(s, PARAMETER_VALUE_ENCODE_SET).into()
+}
+
+/// Decodes a percent-encoded string.
+pub fn decode(s: &str) -> Result<Cow<str>, Error> {
+    percent_decode_str(s)
+        .decode_utf8()
+        .map_err(|e| anyhow!("invalid UTF-8 in decoded {:?}: {}", s, e))
+}
+
+/// Decodes a form-encoded parameter key or value, in which '+' stands for a space.
+pub fn decode_form(s: &str) -> Result<Cow<str>, Error> {
+    if s.contains('+') {
+        decode(s.replace('+', " ").as_str()).map(|d| d.into_owned().into())
+    } else {
+        decode(s)
+    }
+}
@@ -9,6 +9,8 @@ pub enum Extension<'s> {
     Hierarchy,
     NoBody,
     AppKeysList(Cow<'s, str>),
+    LimitHeaders,
+    RejectionReasonHeader,
     Other(Cow<'s, str>, Cow<'s, str>),
 }
 
@@ -20,13 +22,18 @@ impl Extension<'_> {
             Extension::Hierarchy => "hierarchy",
             Extension::AppKeysList(..) => "app_keys_list",
             Extension::NoBody => "no_body",
+            Extension::LimitHeaders => "limit_headers",
+            Extension::RejectionReasonHeader => "rejection_reason_header",
         }
     }
 
     pub fn value(&self) -> &'_ str {
         match self {
             Extension::Other(_, v) | Extension::FlatUsage(v) | Extension::AppKeysList(v) => v,
-            Extension::Hierarchy | Extension::NoBody => "1",
+            Extension::Hierarchy
+            | Extension::NoBody
+            | Extension::LimitHeaders
+            | Extension::RejectionReasonHeader => "1",
         }
     }
 
@@ -40,6 +47,22 @@ impl Extension<'_> {
             Extension::Hierarchy => "hierarchy=1".into(),
             Extension::AppKeysList(value) => Cow::from("app_keys_list=") + value.as_ref(),
             Extension::NoBody => "no_body=1".into(),
+            Extension::LimitHeaders => "limit_headers=1".into(),
+            Extension::RejectionReasonHeader => "rejection_reason_header=1".into(),
+        }
+    }
+
+    pub fn into_owned(self) -> Extension<'static> {
+        match self {
+            Extension::Other(k, v) => {
+                Extension::Other(k.into_owned().into(), v.into_owned().into())
+            }
+            Extension::FlatUsage(v) => Extension::FlatUsage(v.into_owned().into()),
+            Extension::AppKeysList(v) => Extension::AppKeysList(v.into_owned().into()),
+            Extension::Hierarchy => Extension::Hierarchy,
+            Extension::NoBody => Extension::NoBody,
+            Extension::LimitHeaders => Extension::LimitHeaders,
+            Extension::RejectionReasonHeader => Extension::RejectionReasonHeader,
         }
     }
 }
@@ -83,6 +106,14 @@ mod tests {
             Extension::Hierarchy.to_string(),
             Extension::Hierarchy.to_encoded_string()
         );
+        assert_eq!(
+            Extension::LimitHeaders.to_string(),
+            Extension::LimitHeaders.to_encoded_string()
+        );
+        assert_eq!(
+            Extension::RejectionReasonHeader.to_string(),
+            Extension::RejectionReasonHeader.to_encoded_string()
+        );
         assert_eq!(
             Extension::FlatUsage(1.to_string().into()).to_string(),
             Extension::FlatUsage(1.to_string().into()).to_encoded_string()
@@ -1,8 +1,9 @@
 use std::prelude::v1::*;
 
-use std::{borrow::Cow, iter::FromIterator, vec::IntoIter};
+use std::{borrow::Cow, iter::FromIterator, str::FromStr, vec::IntoIter};
 
 use super::Extension;
+use crate::{encoding::decode, Error};
 
 #[repr(transparent)]
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -27,6 +28,10 @@ impl<'s> List<'s> {
         self.0
     }
 
+    pub fn into_owned(self) -> List<'static> {
+        self.0.into_iter().map(Extension::into_owned).collect()
+    }
+
     pub fn as_vec(&self) -> &Vec<Extension<'s>> {
         self.0.as_ref()
     }
@@ -96,6 +101,14 @@ impl<'s> List<'s> {
     pub fn flat_usage(self, level: u32) -> Self {
         self.push(Extension::FlatUsage(level.to_string().into()))
     }
+
+    pub fn limit_headers(self) -> Self {
+        self.push(Extension::LimitHeaders)
+    }
+
+    pub fn rejection_reason_header(self) -> Self {
+        self.push(Extension::RejectionReasonHeader)
+    }
 }
 
 impl ToString for List<'_> {
@@ -108,6 +121,34 @@ impl ToString for List<'_> {
     }
 }
 
+// Parses the contents of the 3scale-options header, the inverse of `to_string`.
+impl FromStr for List<'static> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split('&')
+            .filter(|kv| !kv.is_empty())
+            .map(|kv| {
+                let mut it = kv.splitn(2, '=');
+                let key = decode(it.next().unwrap_or_default())?.into_owned();
+                let value = decode(it.next().unwrap_or_default())?.into_owned();
+
+                let extension = match (key.as_str(), value.as_str()) {
+                    ("flat_usage", _) => Extension::FlatUsage(value.into()),
+                    ("hierarchy", "1") => Extension::Hierarchy,
+                    ("no_body", "1") => Extension::NoBody,
+                    ("app_keys_list", _) => Extension::AppKeysList(value.into()),
+                    ("limit_headers", "1") => Extension::LimitHeaders,
+                    ("rejection_reason_header", "1") => Extension::RejectionReasonHeader,
+                    _ => Extension::Other(key.into(), value.into()),
+                };
+
+                Ok(extension)
+            })
+            .collect()
+    }
+}
+
 impl<'s> Extend<Extension<'s>> for List<'s> {
     fn extend<T: IntoIterator<Item = Extension<'s>>>(&mut self, iter: T) {
         self.0.extend(iter)
@@ -148,3 +189,22 @@ impl<'s> IntoIterator for List<'s> {
         self.0.into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_header_value() {
+        let list = List::new()
+            .no_body()
+            .hierarchy()
+            .flat_usage(1)
+            .limit_headers()
+            .rejection_reason_header()
+            .push_other("some;[]key&%1".into(), "a_^&[]%:;@value".into())
+            .push_other("hierarchy".into(), "0".into());
+
+        assert_eq!(list, list.to_string().parse::<List>().unwrap());
+    }
+}
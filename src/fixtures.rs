@@ -0,0 +1,14 @@
+// Fixtures shared by unit tests.
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+use crate::{application::Application, transaction::Transaction};
+use crate::{credentials::Credentials, service::Service};
+
+pub(crate) fn service() -> Service {
+    Service::new("a_service_id", Credentials::from_token("a_token"))
+}
+
+// A transaction for an application identified by a user key, without usage.
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+pub(crate) fn transaction() -> Transaction<'static> {
+    Transaction::new_owned(Application::from_user_key("a_user_key"), None, None, None)
+}
@@ -47,6 +47,16 @@ impl HeaderMap {
         self.0.insert(key, value)
     }
 
+    /// Returns the value of a header, comparing names case-insensitively.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str).or_else(|| {
+            self.0
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.as_str())
+        })
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -116,6 +126,9 @@ impl From<BTreeMap<String, String>> for HeaderMap {
 
 mod parameters;
 pub use self::parameters::Parameters;
+pub mod batch;
 pub mod endpoints;
 pub mod request;
 pub use self::request::Request;
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+pub mod response;
@@ -0,0 +1,294 @@
+use std::prelude::v1::*;
+
+use std::borrow::Cow;
+
+use crate::{
+    anyhow,
+    api_call::{ApiCall, Kind},
+    extensions::List,
+    service::Service,
+    transaction::Transaction,
+    Error, ToParams,
+};
+
+use super::{endpoints::Endpoints, Method, Parameters, Request};
+
+pub const DEFAULT_MAX_TRANSACTIONS: usize = 1000;
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Splits a number of transactions into as many report requests as needed so that each of them
+/// has at most a maximum number of transactions and a maximum encoded body size.
+///
+/// Transactions are numbered starting from 0 in each of the requests. If a single transaction is
+/// too big to fit in a request an error is returned for it, and the rest keep being processed.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::{application::*, credentials::*, http::batch::ReportBatches, service::*, transaction::*};
+///
+/// let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+/// let apps = (0..10)
+///     .map(|i| Application::from_user_key(format!("user_key_{}", i)))
+///     .collect::<Vec<_>>();
+/// let txns = apps
+///     .iter()
+///     .map(|app| Transaction::new(app, None, None, None))
+///     .collect::<Vec<_>>();
+///
+/// let requests = ReportBatches::new(&service, &txns)
+///     .max_transactions(4)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(requests.len(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReportBatches<'a> {
+    service: &'a Service,
+    transactions: &'a [Transaction<'a>],
+    extensions: Option<&'a List<'a>>,
+    endpoints: Cow<'a, Endpoints>,
+    max_transactions: usize,
+    max_body_size: usize,
+    // cached size of the non-transaction parameters
+    service_size: Option<usize>,
+}
+
+impl<'a> ReportBatches<'a> {
+    pub fn new(service: &'a Service, transactions: &'a [Transaction<'a>]) -> Self {
+        Self {
+            service,
+            transactions,
+            extensions: None,
+            endpoints: Cow::Owned(Endpoints::new()),
+            max_transactions: DEFAULT_MAX_TRANSACTIONS,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            service_size: None,
+        }
+    }
+
+    pub fn extensions(mut self, extensions: &'a List<'a>) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// Sets the paths used for the report endpoint.
+    pub fn endpoints(mut self, endpoints: &'a Endpoints) -> Self {
+        self.endpoints = Cow::Borrowed(endpoints);
+        self
+    }
+
+    /// Sets the maximum number of transactions per request. Values lower than 1 are taken as 1.
+    pub fn max_transactions(mut self, max_transactions: usize) -> Self {
+        self.max_transactions = max_transactions.max(1);
+        self
+    }
+
+    /// Sets the maximum size in bytes of the encoded body of each request.
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Returns the transactions that have not been batched yet.
+    pub fn remaining(&self) -> &'a [Transaction<'a>] {
+        self.transactions
+    }
+
+    fn service_size(&mut self) -> usize {
+        let service = self.service;
+
+        *self.service_size.get_or_insert_with(|| {
+            let mut params = Vec::with_capacity(2);
+            service.to_params(&mut params);
+            encoded_size(params.as_slice())
+        })
+    }
+
+    fn transaction_size(txn: &Transaction, idx: usize) -> usize {
+        let mut params = Vec::with_capacity(8);
+        txn.to_params_with_mangling(&mut params, &mut |c| {
+            format!("transactions[{}]{}", idx, c).into()
+        });
+        // account for the separator from previous parameters
+        1 + encoded_size(params.as_slice())
+    }
+}
+
+fn encoded_size(params: &[(Cow<str>, &str)]) -> usize {
+    Parameters::new(&Method::POST, params)
+        .body()
+        .map_or(0, str::len)
+}
+
+impl Iterator for ReportBatches<'_> {
+    type Item = Result<Request, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.transactions.is_empty() {
+            return None;
+        }
+
+        let mut size = self.service_size();
+        let limit = self.transactions.len().min(self.max_transactions);
+        let mut count = 0;
+
+        while count < limit {
+            let txn_size = Self::transaction_size(&self.transactions[count], count);
+            if size + txn_size > self.max_body_size {
+                break;
+            }
+            size += txn_size;
+            count += 1;
+        }
+
+        if count == 0 {
+            self.transactions = &self.transactions[1..];
+            return Some(Err(anyhow!(
+                "transaction does not fit in a report body of at most {} bytes",
+                self.max_body_size
+            )));
+        }
+
+        let (batch, rest) = self.transactions.split_at(count);
+        self.transactions = rest;
+
+        Some(
+            ApiCall::new(Kind::Report, self.service, batch, self.extensions)
+                .map(|apicall| Request::from_api_call(&apicall, &self.endpoints)),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.transactions.len();
+        let lower = if len == 0 {
+            0
+        } else {
+            1 + (len - 1) / self.max_transactions
+        };
+
+        (lower, Some(len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{application::Application, fixtures::service, usage::Usage};
+
+    fn apps(n: usize) -> Vec<Application> {
+        (0..n)
+            .map(|i| Application::from_user_key(format!("user_key_{}", i)))
+            .collect()
+    }
+
+    #[test]
+    fn splits_by_number_of_transactions() {
+        let service = service();
+        let apps = apps(5);
+        let metrics = [("hits", "1")];
+        let usage = Usage::new(&metrics);
+        let txns = apps
+            .iter()
+            .map(|app| Transaction::new(app, None, Some(&usage), None))
+            .collect::<Vec<_>>();
+
+        let bodies = ReportBatches::new(&service, &txns)
+            .max_transactions(2)
+            .map(|r| r.unwrap().parameters.into_inner())
+            .collect::<Vec<_>>();
+
+        let expected = [
+            "service_id=a_service_id&service_token=a_token\
+             &transactions[0]user_key=user_key_0&transactions[0]usage[hits]=1\
+             &transactions[1]user_key=user_key_1&transactions[1]usage[hits]=1",
+            "service_id=a_service_id&service_token=a_token\
+             &transactions[0]user_key=user_key_2&transactions[0]usage[hits]=1\
+             &transactions[1]user_key=user_key_3&transactions[1]usage[hits]=1",
+            "service_id=a_service_id&service_token=a_token\
+             &transactions[0]user_key=user_key_4&transactions[0]usage[hits]=1",
+        ];
+        assert_eq!(expected.to_vec(), bodies);
+    }
+
+    #[test]
+    fn splits_by_body_size() {
+        let service = service();
+        let apps = apps(4);
+        let txns = apps
+            .iter()
+            .map(|app| Transaction::new(app, None, None, None))
+            .collect::<Vec<_>>();
+        // "service_id=a_service_id&service_token=a_token" is 45 bytes, each transaction adds 35
+        let max_body_size = 45 + 2 * 35;
+
+        let requests = ReportBatches::new(&service, &txns)
+            .max_body_size(max_body_size)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_eq!(2, requests.len());
+        for r in requests {
+            let body = r.parameters.body().unwrap();
+            assert_eq!(max_body_size, body.len());
+            assert!(
+                body.ends_with("&transactions[1]user_key=user_key_1")
+                    || body.ends_with("&transactions[1]user_key=user_key_3")
+            );
+        }
+    }
+
+    #[test]
+    fn errors_on_transactions_too_big_to_fit() {
+        let service = service();
+        let apps = [
+            Application::from_user_key("a"),
+            Application::from_user_key("a_very_long_user_key_that_cannot_fit"),
+            Application::from_user_key("b"),
+        ];
+        let txns = apps
+            .iter()
+            .map(|app| Transaction::new(app, None, None, None))
+            .collect::<Vec<_>>();
+
+        let results = ReportBatches::new(&service, &txns)
+            .max_transactions(1)
+            .max_body_size(80)
+            .collect::<Vec<_>>();
+
+        assert_eq!(3, results.len());
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn uses_configured_endpoints() {
+        let service = service();
+        let apps = apps(3);
+        let txns = apps
+            .iter()
+            .map(|app| Transaction::new(app, None, None, None))
+            .collect::<Vec<_>>();
+        let endpoints = Endpoints::new().with_base_path("/backend");
+
+        let requests = ReportBatches::new(&service, &txns)
+            .endpoints(&endpoints)
+            .max_transactions(2)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_eq!(2, requests.len());
+        for r in requests {
+            assert_eq!("/backend/transactions.xml", r.path);
+        }
+    }
+
+    #[test]
+    fn yields_nothing_without_transactions() {
+        let service = service();
+
+        assert_eq!(0, ReportBatches::new(&service, &[]).count());
+    }
+}
@@ -1,6 +1,11 @@
 /// Static endpoints from the 3scale Apisonator service
 ///
+use std::prelude::v1::*;
+
+use std::borrow::Cow;
+
 use super::Method;
+use crate::api_call::Kind;
 
 pub const AUTHORIZE_ENDPOINT: (Method, &str) = (Method::GET, "/transactions/authorize.xml");
 pub const AUTHREP_ENDPOINT: (Method, &str) = (Method::GET, "/transactions/authrep.xml");
@@ -8,3 +13,202 @@ pub const REPORT_ENDPOINT: (Method, &str) = (Method::POST, "/transactions.xml");
 pub const OAUTH_AUTHORIZE_ENDPOINT: (Method, &str) =
     (Method::GET, "/transactions/oauth_authorize.xml");
 pub const OAUTH_AUTHREP_ENDPOINT: (Method, &str) = (Method::GET, "/transactions/oauth_authrep.xml");
+
+/// The endpoints exposed by Apisonator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Authorize,
+    AuthRep,
+    Report,
+    OAuthAuthorize,
+    OAuthAuthRep,
+}
+
+impl Endpoint {
+    const ALL: [Endpoint; 5] = [
+        Endpoint::Authorize,
+        Endpoint::AuthRep,
+        Endpoint::Report,
+        Endpoint::OAuthAuthorize,
+        Endpoint::OAuthAuthRep,
+    ];
+
+    /// The endpoint serving calls of the given kind, which for authorizations depends on whether
+    /// OAuth is used.
+    pub fn new(kind: Kind, oauth: bool) -> Self {
+        match (kind, oauth) {
+            (Kind::Authorize, false) => Endpoint::Authorize,
+            (Kind::Authorize, true) => Endpoint::OAuthAuthorize,
+            (Kind::AuthRep, false) => Endpoint::AuthRep,
+            (Kind::AuthRep, true) => Endpoint::OAuthAuthRep,
+            (Kind::Report, _) => Endpoint::Report,
+        }
+    }
+
+    pub fn kind(self) -> Kind {
+        match self {
+            Endpoint::Authorize | Endpoint::OAuthAuthorize => Kind::Authorize,
+            Endpoint::AuthRep | Endpoint::OAuthAuthRep => Kind::AuthRep,
+            Endpoint::Report => Kind::Report,
+        }
+    }
+
+    /// The method and path Apisonator serves this endpoint at.
+    pub fn default_method_and_path(self) -> (Method, &'static str) {
+        match self {
+            Endpoint::Authorize => AUTHORIZE_ENDPOINT,
+            Endpoint::AuthRep => AUTHREP_ENDPOINT,
+            Endpoint::Report => REPORT_ENDPOINT,
+            Endpoint::OAuthAuthorize => OAUTH_AUTHORIZE_ENDPOINT,
+            Endpoint::OAuthAuthRep => OAUTH_AUTHREP_ENDPOINT,
+        }
+    }
+
+    pub fn method(self) -> Method {
+        self.default_method_and_path().0
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Paths used to reach each of the endpoints, optionally under a common base path.
+///
+/// The default configuration uses the paths served by Apisonator, so this is only needed when
+/// it is deployed under a different prefix or when talking to a service using other paths.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::http::endpoints::*;
+///
+/// let endpoints = Endpoints::new()
+///     .with_base_path("/backend")
+///     .with_path(Endpoint::Report, "/transactions.json");
+///
+/// assert_eq!(endpoints.path(Endpoint::AuthRep), "/backend/transactions/authrep.xml");
+/// assert_eq!(endpoints.path(Endpoint::Report), "/backend/transactions.json");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    base_path: Cow<'static, str>,
+    paths: [Cow<'static, str>; 5],
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Endpoints {
+    pub fn new() -> Self {
+        let path = |e: Endpoint| Cow::Borrowed(e.default_method_and_path().1);
+
+        Self {
+            base_path: Cow::Borrowed(""),
+            paths: [
+                path(Endpoint::Authorize),
+                path(Endpoint::AuthRep),
+                path(Endpoint::Report),
+                path(Endpoint::OAuthAuthorize),
+                path(Endpoint::OAuthAuthRep),
+            ],
+        }
+    }
+
+    /// Sets a prefix for all endpoint paths. Trailing slashes are removed.
+    pub fn with_base_path<S: Into<Cow<'static, str>>>(mut self, base_path: S) -> Self {
+        let base_path = base_path.into();
+
+        self.base_path = match base_path.trim_end_matches('/') {
+            trimmed if trimmed.len() == base_path.len() => base_path,
+            trimmed => trimmed.to_owned().into(),
+        };
+        self
+    }
+
+    /// Sets the path of an endpoint, relative to the base path.
+    pub fn with_path<S: Into<Cow<'static, str>>>(mut self, endpoint: Endpoint, path: S) -> Self {
+        self.paths[endpoint.index()] = path.into();
+        self
+    }
+
+    pub fn base_path(&self) -> &str {
+        self.base_path.as_ref()
+    }
+
+    /// The full path of an endpoint, including the base path.
+    pub fn path(&self, endpoint: Endpoint) -> Cow<'static, str> {
+        let path = &self.paths[endpoint.index()];
+
+        if self.base_path.is_empty() {
+            path.clone()
+        } else {
+            [self.base_path.as_ref(), path.as_ref()].concat().into()
+        }
+    }
+
+    pub fn method_and_path(&self, endpoint: Endpoint) -> (Method, Cow<'static, str>) {
+        (endpoint.method(), self.path(endpoint))
+    }
+
+    /// Finds the endpoint served at a full path.
+    pub fn find(&self, path: &str) -> Option<Endpoint> {
+        if !path.starts_with(self.base_path.as_ref()) {
+            return None;
+        }
+
+        let path = &path[self.base_path.len()..];
+
+        Endpoint::ALL
+            .iter()
+            .cloned()
+            .find(|e| self.paths[e.index()] == path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_paths_are_borrowed() {
+        let endpoints = Endpoints::default();
+
+        for &e in Endpoint::ALL.iter() {
+            let path = endpoints.path(e);
+
+            assert!(matches!(path, Cow::Borrowed(_)));
+            assert_eq!(e.default_method_and_path().1, path);
+            assert_eq!(Some(e), endpoints.find(&path));
+        }
+    }
+
+    #[test]
+    fn prefixes_base_path() {
+        let endpoints = Endpoints::new()
+            .with_base_path("/backend/")
+            .with_path(Endpoint::OAuthAuthRep, "/oauth_authrep.json".to_string());
+
+        assert_eq!("/backend", endpoints.base_path());
+        assert_eq!(
+            "/backend/transactions/authorize.xml",
+            endpoints.path(Endpoint::Authorize)
+        );
+        assert_eq!(
+            (Method::GET, "/backend/oauth_authrep.json".into()),
+            endpoints.method_and_path(Endpoint::OAuthAuthRep)
+        );
+        assert_eq!(
+            Some(Endpoint::Report),
+            endpoints.find("/backend/transactions.xml")
+        );
+        assert_eq!(None, endpoints.find("/transactions.xml"));
+        assert_eq!(
+            None,
+            endpoints.find("/backend/transactions/oauth_authrep.xml")
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::prelude::v1::*;
 
 use super::Method;
+use crate::encoding::{encode_key, encode_value};
 use std::borrow::Cow;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -101,12 +102,14 @@ impl Parameters {
         s.push_str(q.as_str());
     }
 
+    // Both keys and values are percent-encoded, but note that keys are expected to follow the
+    // Rack format, so brackets in them are kept as is.
     fn params_to_string_collection<'p, 'a: 'p, S: AsRef<str>>(
         params: &'a [(Cow<str>, S)],
     ) -> ParamsMapper<'a, 'p, S, String> {
         params
             .iter()
-            .map(|(k, v)| [k.as_ref(), "=", v.as_ref()].concat())
+            .map(|(k, v)| [encode_key(k.as_ref()), "=".into(), encode_value(v.as_ref())].concat())
     }
 
     fn params_to_vec<S: AsRef<str>>(params: &[(Cow<str>, S)]) -> Vec<String> {
@@ -118,6 +121,51 @@ impl Parameters {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_values() {
+        let params: Vec<(Cow<str>, &str)> = vec![
+            ("user_key".into(), "a key&b=c+d [e]"),
+            ("service_token".into(), "t%k/ñ"),
+        ];
+        let parameters = Parameters::new(&Method::GET, params.as_slice());
+
+        assert_eq!(
+            Some("user_key=a%20key%26b%3Dc%2Bd%20%5Be%5D&service_token=t%25k%2F%C3%B1"),
+            parameters.query()
+        );
+    }
+
+    #[test]
+    fn encodes_keys_keeping_brackets() {
+        let params: Vec<(Cow<str>, &str)> = vec![
+            ("transactions[0][usage][hits]".into(), "1"),
+            ("transactions[1][usage][a metric&=]".into(), "2"),
+        ];
+        let parameters = Parameters::new(&Method::POST, params.as_slice());
+
+        assert_eq!(
+            Some("transactions[0][usage][hits]=1&transactions[1][usage][a%20metric%26%3D]=2"),
+            parameters.body()
+        );
+    }
+
+    #[test]
+    fn push_encodes_extra_params() {
+        let params: Vec<(Cow<str>, &str)> = vec![("app_id".into(), "an app")];
+        let mut parameters = Parameters::new(&Method::GET, params.as_slice());
+        parameters.push(&[(Cow::from("referrer"), "http://example.com/?a=b")]);
+
+        assert_eq!(
+            Some("app_id=an%20app&referrer=http%3A%2F%2Fexample.com%2F%3Fa%3Db"),
+            parameters.query()
+        );
+    }
+}
+
 // can't test directly for test::Bencher because autocfg lacks support for now,
 // so use feature_test which is already a guarantee of running nightly.
 #[cfg(all(test, feature_test))]
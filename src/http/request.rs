@@ -1,28 +1,30 @@
 use std::prelude::v1::*;
 
-use crate::{
-    api_call::{Kind::*, *},
-    application::*,
-    user::*,
-    version::USER_AGENT,
-    ToParams,
-};
+use crate::{api_call::*, application::*, user::*, version::USER_AGENT, ToParams};
 
-use super::Parameters;
+use super::{
+    endpoints::{Endpoint, Endpoints},
+    Parameters,
+};
 
 #[cfg(any(feature = "curl-easy", feature = "curl-easy2"))]
 pub mod curl;
 #[cfg(feature = "http-types")]
 mod http_types;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+mod parse;
 #[cfg(any(feature = "reqwest-sync", feature = "reqwest-async"))]
 mod reqwest;
+#[cfg(feature = "ureq")]
+pub mod ureq;
 
 pub use super::{HeaderMap, Method};
 
 #[derive(Clone, Debug)]
 pub struct Request {
     pub method: Method,
-    pub path: &'static str,
+    pub path: Cow<'static, str>,
     pub parameters: Parameters,
     pub headers: HeaderMap,
 }
@@ -30,45 +32,43 @@ pub struct Request {
 use std::borrow::Cow;
 
 impl Request {
+    /// Returns the default method and path for a call. See `Endpoints` to use other paths.
     pub fn endpoint(
         kind: Kind,
         application: Option<&Application>,
         user: Option<&User>,
     ) -> (Method, &'static str) {
-        use super::endpoints::*;
-
-        match (kind, application, user) {
-            (Authorize, Some(Application::OAuthToken(_)), _) => OAUTH_AUTHORIZE_ENDPOINT,
-            (Authorize, _, Some(&User::OAuthToken(_))) => OAUTH_AUTHORIZE_ENDPOINT,
-            (Authorize, ..) => AUTHORIZE_ENDPOINT,
-            (AuthRep, Some(Application::OAuthToken(_)), _) => OAUTH_AUTHREP_ENDPOINT,
-            (AuthRep, _, Some(&User::OAuthToken(_))) => OAUTH_AUTHREP_ENDPOINT,
-            (AuthRep, ..) => AUTHREP_ENDPOINT,
-            (Report, ..) => REPORT_ENDPOINT,
-        }
+        Endpoint::new(kind, kind.is_oauth(application, user)).default_method_and_path()
     }
 
-    pub fn uri_and_body(&self) -> (Cow<str>, Option<&str>) {
-        (
-            self.parameters.path_and_query(self.path),
-            self.parameters.body(),
-        )
+    /// Builds the request for a call using the given endpoint paths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::{api_call::*, application::*, credentials::*, http::{endpoints::*, Request}, service::*, transaction::*};
+    ///
+    /// let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+    /// let app = Application::from_user_key("my_user_key");
+    /// let txn = Transaction::new(&app, None, None, None);
+    /// let apicall = ApiCall::builder(&service).authrep().transaction(&txn).build();
+    /// let endpoints = Endpoints::new().with_base_path("/backend");
+    ///
+    /// let request = Request::from_api_call(&apicall, &endpoints);
+    ///
+    /// assert_eq!(request.path, "/backend/transactions/authrep.xml");
+    /// ```
+    pub fn from_api_call(apicall: &ApiCall, endpoints: &Endpoints) -> Self {
+        let kind = apicall.kind();
+        let endpoint = Endpoint::new(kind, kind.is_oauth(apicall.application(), apicall.user()));
+
+        Self::from_api_call_to(apicall, endpoints, endpoint)
     }
-}
-
-/// This trait needs to be implemented by each client to set up a specific request.
-///
-/// The 'client lifetime will be useful if your Output return value needs to get hold of it. Such
-/// is the case of curl's Easy client when sending POST requests via their Transfer<'client, 'data>
-/// type, but for other clients which don't need to wrap the original client it's simply elided.
-pub trait SetupRequest<'client, P, Output> {
-    fn setup_request(&'client mut self, r: Request, params: P) -> Output;
-}
 
-impl From<&ApiCall<'_>> for Request {
-    fn from(apicall: &ApiCall) -> Self {
-        let (method, path) =
-            Request::endpoint(apicall.kind(), apicall.application(), apicall.user());
+    /// Builds the request for a call to a specific endpoint, ie. to use the OAuth endpoints with
+    /// credentials other than OAuth tokens.
+    pub fn from_api_call_to(apicall: &ApiCall, endpoints: &Endpoints, endpoint: Endpoint) -> Self {
+        let (method, path) = endpoints.method_and_path(endpoint);
 
         let mut params = Vec::with_capacity(8);
         apicall.to_params(&mut params);
@@ -93,4 +93,26 @@ impl From<&ApiCall<'_>> for Request {
             headers,
         }
     }
+
+    pub fn uri_and_body(&self) -> (Cow<str>, Option<&str>) {
+        (
+            self.parameters.path_and_query(&self.path),
+            self.parameters.body(),
+        )
+    }
+}
+
+/// This trait needs to be implemented by each client to set up a specific request.
+///
+/// The 'client lifetime will be useful if your Output return value needs to get hold of it. Such
+/// is the case of curl's Easy client when sending POST requests via their Transfer<'client, 'data>
+/// type, but for other clients which don't need to wrap the original client it's simply elided.
+pub trait SetupRequest<'client, P, Output> {
+    fn setup_request(&'client mut self, r: Request, params: P) -> Output;
+}
+
+impl From<&ApiCall<'_>> for Request {
+    fn from(apicall: &ApiCall) -> Self {
+        Request::from_api_call(apicall, &Endpoints::default())
+    }
 }
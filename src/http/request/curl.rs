@@ -31,6 +31,56 @@ pub fn copy_data(offset: &mut usize, source: &[u8], dst: &mut [u8]) -> usize {
     len
 }
 
+/// Collects the headers and body of the responses received by curl clients, to be fed from the
+/// header and write callbacks.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseData {
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl ResponseData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores a header line, returning true as curl's header callbacks expect.
+    pub fn header(&mut self, line: &[u8]) -> bool {
+        if let Ok(line) = core::str::from_utf8(line) {
+            if line.starts_with("HTTP/") {
+                // a new response is starting, ie. after a redirect
+                self.clear();
+            } else if let Some(idx) = line.find(':') {
+                self.headers.insert(
+                    line[..idx].trim().to_owned(),
+                    line[idx + 1..].trim().to_owned(),
+                );
+            }
+        }
+
+        true
+    }
+
+    /// Stores body data, returning the number of bytes taken as curl's write callbacks expect.
+    pub fn write(&mut self, data: &[u8]) -> usize {
+        self.body.extend_from_slice(data);
+        data.len()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn body(&self) -> &[u8] {
+        self.body.as_slice()
+    }
+
+    pub fn clear(&mut self) {
+        self.headers = HeaderMap::new();
+        self.body.clear();
+    }
+}
+
 #[cfg(feature = "curl-easy")]
 mod easy;
 #[cfg(feature = "curl-easy2")]
@@ -39,4 +89,27 @@ mod easy2;
 #[cfg(feature = "curl-easy")]
 pub use easy::CurlEasyClient;
 #[cfg(feature = "curl-easy2")]
-pub use easy2::{BodyHandle, SetBody};
+pub use easy2::{BodyHandle, CollectResponse, SetBody};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_response_data() {
+        let mut data = ResponseData::new();
+
+        assert!(data.header(b"HTTP/1.1 301 Moved Permanently\r\n"));
+        assert!(data.header(b"Location: https://su1.3scale.net/\r\n"));
+        assert_eq!(data.write(b"moved"), 5);
+        // only the data from the last response is kept
+        assert!(data.header(b"HTTP/1.1 200 OK\r\n"));
+        assert!(data.header(b"3scale-limit-remaining: 9\r\n"));
+        assert!(data.header(b"\r\n"));
+        assert_eq!(data.write(b"<status>"), 8);
+
+        assert_eq!(data.headers().get("Location"), None);
+        assert_eq!(data.headers().get("3scale-limit-remaining"), Some("9"));
+        assert_eq!(data.body(), b"<status>");
+    }
+}
@@ -5,6 +5,11 @@ use crate::{anyhow, Error};
 use super::super::{Method, Request, SetupRequest};
 use curl::easy::{Easy, List, Transfer};
 
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+use super::ResponseData;
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+use crate::http::response::{typed_response, FromResponseParts, ReadResponse, ResponseError};
+
 #[derive(Debug)]
 pub enum CurlEasyClient<'easy, 'data> {
     Easy(&'easy Easy),
@@ -61,7 +66,7 @@ impl<'easy, 'data, URI: ToString>
     ) -> Result<CurlEasyClient<'easy, 'data>, Error> {
         use core::convert::TryFrom;
 
-        let (uri, body) = r.parameters.uri_and_body(r.path);
+        let (uri, body) = r.parameters.uri_and_body(&r.path);
         let uri_base = params;
         let uri = uri_base.to_string() + uri.as_ref();
 
@@ -110,3 +115,14 @@ impl<'easy, 'data, URI: ToString>
         })
     }
 }
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+impl<T: FromResponseParts> ReadResponse<&ResponseData, Result<T, ResponseError>> for &Easy {
+    fn read_response(self, data: &ResponseData) -> Result<T, ResponseError> {
+        let status = self.response_code().map_err(|e| {
+            ResponseError::Transport(anyhow!("failed to get curl response code: {:#?}", e))
+        })?;
+
+        typed_response(status as u16, Ok(data.headers().clone()), data.body())
+    }
+}
@@ -3,7 +3,11 @@ use std::prelude::v1::*;
 use crate::{anyhow, Error, Result};
 
 use super::super::{Method, Request, SetupRequest};
-use curl::easy::{Easy2, Handler, List, ReadError};
+use curl::easy::{Easy2, Handler, List, ReadError, WriteError};
+
+use super::ResponseData;
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+use crate::http::response::{typed_response, FromResponseParts, ReadResponse, ResponseError};
 
 /// This trait has to be implemented by the Easy2<H>'s H generic type, as well as curl's Handler.
 /// This is because the body of POST requests needs to be pushed from the storage associated to
@@ -21,12 +25,20 @@ pub trait SetBody: Handler {
     fn set_body(&mut self, body: String);
 }
 
+/// This trait has to be implemented by the Easy2<H>'s H generic type to read responses, storing
+/// the headers and body received from its Handler callbacks.
+pub trait CollectResponse: Handler {
+    fn response_data(&self) -> &ResponseData;
+}
+
 /// A default type that works with the requirements of conversion between a Request and a set up
-/// Easy2 client by implementing the SetBody trait.
+/// Easy2 client by implementing the SetBody trait, and that collects responses by implementing
+/// the CollectResponse trait.
 #[derive(Debug, Clone)]
 pub struct BodyHandle {
     count: usize,
     body: Option<String>,
+    response: ResponseData,
 }
 
 impl BodyHandle {
@@ -34,6 +46,7 @@ impl BodyHandle {
         Self {
             count: 0,
             body: None,
+            response: ResponseData::new(),
         }
     }
 
@@ -60,6 +73,20 @@ impl Handler for BodyHandle {
             unreachable!()
         }
     }
+
+    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        Ok(self.response.write(data))
+    }
+
+    fn header(&mut self, data: &[u8]) -> bool {
+        self.response.header(data)
+    }
+}
+
+impl CollectResponse for BodyHandle {
+    fn response_data(&self) -> &ResponseData {
+        &self.response
+    }
 }
 
 impl SetBody for BodyHandle {
@@ -72,7 +99,7 @@ impl<URI: ToString, H: SetBody> SetupRequest<'_, URI, Result<(), Error>> for Eas
     fn setup_request(&mut self, r: Request, params: URI) -> Result<(), Error> {
         use core::convert::TryFrom;
 
-        let (uri, body) = r.parameters.uri_and_body(r.path);
+        let (uri, body) = r.parameters.uri_and_body(&r.path);
         let uri_base = params;
         let uri = uri_base.to_string() + uri.as_ref();
 
@@ -112,3 +139,17 @@ impl<URI: ToString, H: SetBody> SetupRequest<'_, URI, Result<(), Error>> for Eas
         Ok(())
     }
 }
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+impl<T: FromResponseParts, H: CollectResponse> ReadResponse<(), Result<T, ResponseError>>
+    for &Easy2<H>
+{
+    fn read_response(self, _params: ()) -> Result<T, ResponseError> {
+        let status = self.response_code().map_err(|e| {
+            ResponseError::Transport(anyhow!("failed to get curl response code: {:#?}", e))
+        })?;
+        let data = self.get_ref().response_data();
+
+        typed_response(status as u16, Ok(data.headers().clone()), data.body())
+    }
+}
@@ -63,11 +63,26 @@ impl TryFrom<HeaderMap> for HTTPHeaderMap {
     }
 }
 
+impl TryFrom<&HTTPHeaderMap> for HeaderMap {
+    type Error = Error;
+
+    fn try_from(hm: &HTTPHeaderMap) -> Result<Self, Self::Error> {
+        hm.iter()
+            .map(|(key, value)| {
+                value
+                    .to_str()
+                    .map(|value| (key.as_str(), value))
+                    .map_err(|e| anyhow!("failed to convert value of header {}: {:#?}", key, e))
+            })
+            .collect()
+    }
+}
+
 impl TryFrom<Request> for HTTPRequest<String> {
     type Error = Error;
 
     fn try_from(r: Request) -> Result<Self, Self::Error> {
-        let (uri, body) = r.parameters.uri_and_body(r.path);
+        let (uri, body) = r.parameters.uri_and_body(&r.path);
         let body = body.unwrap_or("").to_owned();
         let rb = HTTPRequest::builder();
 
@@ -103,3 +118,33 @@ impl SetupRequest<'_, Never, Result<HTTPRequest<String>, Error>> for Builder {
             .map_err(|e| anyhow!("failed to convert request to http's Request: {:#?}", e))
     }
 }
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+mod response {
+    use std::prelude::v1::*;
+
+    use super::{HeaderMap, TryFrom};
+    use crate::http::response::{typed_response, FromResponseParts, ReadResponse, ResponseError};
+    use http_types::Response as HTTPResponse;
+
+    // A blanket impl over AsRef<[u8]> bodies would conflict with the one for hyper's Body.
+    macro_rules! http_response_impl {
+        { $($B:ty),* } => {
+            $(
+                impl<T: FromResponseParts> ReadResponse<(), Result<T, ResponseError>>
+                    for HTTPResponse<$B>
+                {
+                    fn read_response(self, _params: ()) -> Result<T, ResponseError> {
+                        typed_response(
+                            self.status().as_u16(),
+                            HeaderMap::try_from(self.headers()),
+                            AsRef::<[u8]>::as_ref(self.body()),
+                        )
+                    }
+                }
+            )*
+        }
+    }
+
+    http_response_impl!(String, Vec<u8>, &str, &[u8]);
+}
@@ -0,0 +1,200 @@
+use std::prelude::v1::*;
+
+use super::{HeaderMap, Request, SetupRequest};
+use crate::{anyhow, version::USER_AGENT, Error};
+use core::convert::TryFrom;
+use hyper_crate::{
+    header::{HeaderValue, USER_AGENT as USER_AGENT_HEADER},
+    http::request::Builder,
+    Body, HeaderMap as HyperHeaderMap, Method as HyperMethod, Request as HyperRequest,
+};
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+use crate::http::response::{
+    typed_response, FromResponseParts, ReadResponse, ResponseError, ResponseFuture,
+};
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+use crate::response::{AuthorizationResponse, ReportResponse};
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+use hyper_crate::Response as HyperResponse;
+
+// Like reqwest, hyper needs an absolute URI to send a request, so a base URI such as
+// https://a_host is required. Any configuration set in the builder, such as the HTTP version or
+// a User-Agent, is kept.
+impl<URI: ToString> SetupRequest<'_, URI, Result<HyperRequest<Body>, Error>> for Builder {
+    fn setup_request(&mut self, r: Request, params: URI) -> Result<HyperRequest<Body>, Error> {
+        let (uri, body) = r.parameters.uri_and_body(&r.path);
+        let uri = params.to_string() + uri.as_ref();
+        let body = match body {
+            // consume the body from the request's parameters to avoid cloning it
+            Some(_) => Body::from(r.parameters.into_inner()),
+            _ => Body::empty(),
+        };
+
+        let mut headers = HyperHeaderMap::try_from(r.headers)
+            .map_err(|e| anyhow!("failed to add headers to hyper's Request: {:#?}", e))?;
+
+        let mut request = core::mem::take(self)
+            .method(HyperMethod::from(r.method))
+            .uri(uri.as_str())
+            .body(body)
+            .map_err(|e| anyhow!("failed to build hyper's Request: {:#?}", e))?;
+        // keep any User-Agent set in the builder
+        if request.headers().contains_key(USER_AGENT_HEADER) {
+            headers.remove(USER_AGENT_HEADER);
+        }
+        request.headers_mut().extend(headers);
+        request
+            .headers_mut()
+            .entry(USER_AGENT_HEADER)
+            .or_insert_with(|| HeaderValue::from_static(USER_AGENT));
+
+        Ok(request)
+    }
+}
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+fn response_parts<B: AsRef<[u8]>>(
+    response: &HyperResponse<B>,
+) -> Result<(u16, HeaderMap, &str), Error> {
+    let headers = HeaderMap::try_from(response.headers())?;
+    let body = core::str::from_utf8(response.body().as_ref())
+        .map_err(|e| anyhow!("response body is not valid UTF-8: {}", e))?;
+
+    Ok((response.status().as_u16(), headers, body))
+}
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+impl<B: AsRef<[u8]>> TryFrom<&HyperResponse<B>> for AuthorizationResponse {
+    type Error = Error;
+
+    fn try_from(response: &HyperResponse<B>) -> Result<Self, Self::Error> {
+        let (status, headers, body) = response_parts(response)?;
+
+        AuthorizationResponse::from_parts(status, &headers, Some(body))
+    }
+}
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+impl<B: AsRef<[u8]>> TryFrom<&HyperResponse<B>> for ReportResponse {
+    type Error = Error;
+
+    fn try_from(response: &HyperResponse<B>) -> Result<Self, Self::Error> {
+        let (status, headers, body) = response_parts(response)?;
+
+        ReportResponse::from_parts(status, &headers, Some(body))
+    }
+}
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+impl<T: FromResponseParts + Send + 'static> ReadResponse<(), ResponseFuture<T>>
+    for HyperResponse<Body>
+{
+    fn read_response(self, _params: ()) -> ResponseFuture<T> {
+        Box::pin(async move {
+            let (parts, body) = self.into_parts();
+            let body = hyper_crate::body::to_bytes(body).await.map_err(|e| {
+                ResponseError::Transport(anyhow!("failed to read hyper's response body: {:#?}", e))
+            })?;
+
+            typed_response(
+                parts.status.as_u16(),
+                HeaderMap::try_from(&parts.headers),
+                body.as_ref(),
+            )
+        })
+    }
+}
+
+/// Reads the body of a response to an authorization and parses it.
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+pub async fn authorization_response(
+    response: HyperResponse<Body>,
+) -> Result<AuthorizationResponse, Error> {
+    response
+        .read_response(())
+        .await
+        .map_err(ResponseError::into_inner)
+}
+
+/// Reads the body of a response to a report and parses it.
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+pub async fn report_response(response: HyperResponse<Body>) -> Result<ReportResponse, Error> {
+    response
+        .read_response(())
+        .await
+        .map_err(ResponseError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api_call::*, application::*, credentials::*, service::*, transaction::*};
+
+    #[test]
+    fn sets_up_requests() {
+        let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+        let app = Application::from_user_key("my_user_key");
+        let txn = Transaction::new(&app, None, None, None);
+        let apicall = ApiCall::builder(&service)
+            .authrep()
+            .transaction(&txn)
+            .build();
+
+        let request = HyperRequest::builder()
+            .setup_request(Request::from(&apicall), "https://su1.3scale.net")
+            .unwrap();
+
+        assert_eq!(request.method(), HyperMethod::GET);
+        assert_eq!(
+            request.uri(),
+            "https://su1.3scale.net/transactions/authrep.xml?service_id=my_service_id&service_token=my_token&user_key=my_user_key"
+        );
+        assert_eq!(
+            request.headers().get_all(USER_AGENT_HEADER).iter().count(),
+            1
+        );
+    }
+
+    #[test]
+    fn keeps_builder_user_agent() {
+        let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+        let app = Application::from_user_key("my_user_key");
+        let txn = Transaction::new(&app, None, None, None);
+        let apicall = ApiCall::builder(&service)
+            .authorize()
+            .transaction(&txn)
+            .build();
+
+        let request = HyperRequest::builder()
+            .header(USER_AGENT_HEADER, "my-gateway/1.0")
+            .setup_request(Request::from(&apicall), "https://su1.3scale.net")
+            .unwrap();
+
+        let user_agents = request
+            .headers()
+            .get_all(USER_AGENT_HEADER)
+            .iter()
+            .collect::<Vec<_>>();
+        assert_eq!(user_agents, ["my-gateway/1.0"]);
+    }
+
+    #[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+    #[test]
+    fn converts_responses() {
+        let response = HyperResponse::builder()
+            .status(409)
+            .header("3scale-rejection-reason", "limits_exceeded")
+            .body("<error code=\"limits_exceeded\">usage limits are exceeded</error>")
+            .unwrap();
+
+        let response = AuthorizationResponse::try_from(&response).unwrap();
+
+        assert_eq!(response.status(), 409);
+        assert!(!response.is_authorized());
+        assert_eq!(
+            response.rejection_reason().map(|code| code.as_str()),
+            Some("limits_exceeded")
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use std::prelude::v1::*;
+
+use core::convert::TryFrom;
+use std::collections::BTreeMap;
+
+use crate::{
+    anyhow,
+    api_call::ApiCall,
+    application::Application,
+    credentials::Credentials,
+    encoding::decode_form,
+    extensions::List,
+    http::{
+        endpoints::{Endpoint, Endpoints},
+        HeaderMap, Method, Parameters,
+    },
+    log::{Log, DEFAULT_MAX_REQUEST_LENGTH, DEFAULT_MAX_RESPONSE_LENGTH},
+    service::Service,
+    transaction::{Redirect, Transaction},
+    usage::{MetricUsage, Usage},
+    user::User,
+    Error,
+};
+
+use super::Request;
+
+fn find_endpoint(endpoints: &Endpoints, path: &str) -> Result<Endpoint, Error> {
+    endpoints
+        .find(path)
+        .ok_or_else(|| anyhow!("unknown endpoint {:?}", path))
+}
+
+impl Request {
+    /// Builds a `Request` out of the parts of an HTTP request sent to Apisonator, so that it can
+    /// be parsed back into an `ApiCall`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use threescalers::{api_call::*, http::{HeaderMap, Method, Request}};
+    ///
+    /// let request = Request::from_parts(
+    ///     Method::GET,
+    ///     "/transactions/authrep.xml?service_id=my_service_id&service_token=my_token&user_key=my_user_key&usage[hits]=1",
+    ///     None,
+    ///     HeaderMap::new(),
+    /// ).unwrap();
+    /// let apicall = ApiCall::try_from(&request).unwrap();
+    ///
+    /// assert_eq!(apicall.kind(), Kind::AuthRep);
+    /// assert_eq!(apicall.usage().unwrap().as_vec()[0].value(), "1");
+    /// ```
+    pub fn from_parts(
+        method: Method,
+        path_and_query: &str,
+        body: Option<&str>,
+        headers: HeaderMap,
+    ) -> Result<Self, Error> {
+        Self::from_parts_with_endpoints(method, path_and_query, body, headers, &Endpoints::new())
+    }
+
+    /// Builds a `Request` out of the parts of an HTTP request, recognizing the given endpoints.
+    pub fn from_parts_with_endpoints(
+        method: Method,
+        path_and_query: &str,
+        body: Option<&str>,
+        headers: HeaderMap,
+        endpoints: &Endpoints,
+    ) -> Result<Self, Error> {
+        let mut it = path_and_query.splitn(2, '?');
+        let path = it.next().unwrap_or_default();
+        let query = it.next().unwrap_or_default();
+
+        let (endpoint_method, path) = endpoints.method_and_path(find_endpoint(endpoints, path)?);
+        if method != endpoint_method {
+            return Err(anyhow!(
+                "unexpected method {} for endpoint {}",
+                method.as_str(),
+                path
+            ));
+        }
+
+        let parameters = if endpoint_method == Method::GET {
+            Parameters::Query(query.to_owned())
+        } else if query.is_empty() {
+            Parameters::Body(body.unwrap_or_default().to_owned())
+        } else {
+            return Err(anyhow!("unexpected query string for endpoint {}", path));
+        };
+
+        Ok(Request {
+            method,
+            path,
+            parameters,
+            headers,
+        })
+    }
+}
+
+/// Parses a `Request` using the default endpoints. See `ApiCall::from_request`.
+impl TryFrom<&Request> for ApiCall<'static> {
+    type Error = Error;
+
+    fn try_from(request: &Request) -> Result<Self, Self::Error> {
+        ApiCall::from_request(request, &Endpoints::new())
+    }
+}
+
+impl ApiCall<'static> {
+    /// Parses a `Request` back into the `ApiCall` it was built from, taking extensions from the
+    /// 3scale-options header.
+    ///
+    /// Report transactions are accepted both as `transactions[0]user_key` and in the fully
+    /// nested Rack format, ie. `transactions[0][user_key]`.
+    pub fn from_request(request: &Request, endpoints: &Endpoints) -> Result<Self, Error> {
+        let kind = find_endpoint(endpoints, &request.path)?.kind();
+        let params = match &request.parameters {
+            Parameters::Query(params) | Parameters::Body(params) => params,
+        };
+
+        let mut service_id = None;
+        let mut credentials = None;
+        let mut transactions = BTreeMap::new();
+
+        if !kind.is_report() {
+            transactions.insert(0, TransactionFields::default());
+        }
+
+        for kv in params.split('&').filter(|kv| !kv.is_empty()) {
+            let mut it = kv.splitn(2, '=');
+            let key = decode_form(it.next().unwrap_or_default())?;
+            let value = decode_form(it.next().unwrap_or_default())?.into_owned();
+
+            match key.as_ref() {
+                "service_id" => set_once(&mut service_id, &key, value)?,
+                "provider_key" => set_once(&mut credentials, &key, Credentials::from_key(value))?,
+                "service_token" => {
+                    set_once(&mut credentials, &key, Credentials::from_token(value))?
+                }
+                key if kind.is_report() => {
+                    let (idx, field) =
+                        transaction_field(key).ok_or_else(|| unexpected_parameter(key))?;
+                    if is_authorization_field(field.as_str()) {
+                        return Err(unexpected_parameter(key));
+                    }
+                    transactions
+                        .entry(idx)
+                        .or_insert_with(TransactionFields::default)
+                        .set(field.as_str(), value)?;
+                }
+                field => transactions
+                    .get_mut(&0)
+                    .expect("non-report calls have a transaction")
+                    .set(field, value)?,
+            }
+        }
+
+        let service_id = service_id.ok_or_else(|| anyhow!("missing service_id"))?;
+        let credentials = credentials.ok_or_else(|| anyhow!("missing service credentials"))?;
+        // BTreeMap::into_values is not available in our MSRV
+        #[allow(clippy::iter_kv_map)]
+        let transactions = transactions
+            .into_iter()
+            .map(|(_, fields)| fields.into_transaction())
+            .collect::<Result<Vec<_>, _>>()?;
+        let extensions = request
+            .headers
+            .get("3scale-options")
+            .map(str::parse::<List>)
+            .transpose()?;
+
+        ApiCall::new_owned(
+            kind,
+            Service::new(service_id, credentials),
+            transactions,
+            extensions,
+        )
+    }
+}
+
+fn set_once<T>(slot: &mut Option<T>, key: &str, value: T) -> Result<(), Error> {
+    if slot.is_some() {
+        return Err(anyhow!("duplicate parameter {:?}", key));
+    }
+
+    *slot = Some(value);
+    Ok(())
+}
+
+fn unexpected_parameter(key: &str) -> Error {
+    anyhow!("unexpected parameter {:?}", key)
+}
+
+fn is_authorization_field(field: &str) -> bool {
+    matches!(field, "referrer" | "redirect_url" | "redirect_uri")
+}
+
+// Splits a report key into the transaction index and its field, normalizing the nested Rack
+// format so that both transactions[0][usage][hits] and transactions[0]usage[hits] are accepted.
+fn transaction_field(key: &str) -> Option<(usize, String)> {
+    const PREFIX: &str = "transactions[";
+
+    if !key.starts_with(PREFIX) {
+        return None;
+    }
+
+    let rest = &key[PREFIX.len()..];
+    let end = rest.find(']')?;
+    let idx = rest[..end].parse().ok()?;
+    let field = &rest[end + 1..];
+
+    if field.starts_with('[') {
+        let end = field.find(']')?;
+        Some((idx, [&field[1..end], &field[end + 1..]].concat()))
+    } else {
+        Some((idx, field.to_owned()))
+    }
+}
+
+// Parameters of a single transaction as found in a request.
+#[derive(Debug, Default)]
+struct TransactionFields {
+    app_id: Option<String>,
+    app_key: Option<String>,
+    user_key: Option<String>,
+    // both applications and users can be identified by access tokens, in that order
+    access_tokens: Vec<String>,
+    user_id: Option<String>,
+    usage: Vec<MetricUsage<'static>>,
+    timestamp: Option<String>,
+    log_request: Option<String>,
+    log_response: Option<String>,
+    log_code: Option<u16>,
+    referrer: Option<String>,
+    redirect: Option<Redirect<'static>>,
+}
+
+impl TransactionFields {
+    fn set(&mut self, field: &str, value: String) -> Result<(), Error> {
+        match field {
+            "app_id" => set_once(&mut self.app_id, field, value),
+            "app_key" => set_once(&mut self.app_key, field, value),
+            "user_key" => set_once(&mut self.user_key, field, value),
+            "access_token" => {
+                self.access_tokens.push(value);
+                Ok(())
+            }
+            "user_id" => set_once(&mut self.user_id, field, value),
+            "timestamp" => set_once(&mut self.timestamp, field, value),
+            "log[request]" => set_once(&mut self.log_request, field, value),
+            "log[response]" => set_once(&mut self.log_response, field, value),
+            "log[code]" => {
+                let code = value
+                    .parse()
+                    .map_err(|_| anyhow!("invalid log code {:?}", value))?;
+                set_once(&mut self.log_code, field, code)
+            }
+            "referrer" => set_once(&mut self.referrer, field, value),
+            "redirect_url" => set_once(&mut self.redirect, field, Redirect::Url(value.into())),
+            "redirect_uri" => set_once(&mut self.redirect, field, Redirect::Uri(value.into())),
+            _ if field.starts_with("usage[") && field.ends_with(']') && field.len() > 6 => {
+                let metric = &field["usage[".len()..field.len() - 1];
+                self.usage.push(MetricUsage::new(metric.to_owned(), value));
+                Ok(())
+            }
+            _ => Err(unexpected_parameter(field)),
+        }
+    }
+
+    fn into_transaction(self) -> Result<Transaction<'static>, Error> {
+        let mut access_tokens = self.access_tokens.into_iter();
+
+        let application = match (self.app_id, self.app_key, self.user_key) {
+            (Some(app_id), app_key, None) => {
+                Application::AppId(app_id.into(), app_key.map(Into::into))
+            }
+            (None, None, Some(user_key)) => Application::from_user_key(user_key),
+            (None, None, None) => access_tokens
+                .next()
+                .map(Application::from_oauth_token)
+                .ok_or_else(|| anyhow!("missing application credentials"))?,
+            _ => return Err(anyhow!("conflicting application credentials")),
+        };
+
+        let user = match (self.user_id, access_tokens.next()) {
+            (None, None) => None,
+            (Some(user_id), None) => Some(User::from_user_id(user_id)),
+            (None, Some(token)) => Some(User::from_oauth_token(token)),
+            _ => return Err(anyhow!("conflicting user credentials")),
+        };
+
+        if access_tokens.next().is_some() {
+            return Err(anyhow!("duplicate parameter \"access_token\""));
+        }
+
+        let usage = if self.usage.is_empty() {
+            None
+        } else {
+            Some(Usage::from(self.usage))
+        };
+
+        let mut txn = Transaction::new_owned(application, user, usage, None);
+
+        if let Some(timestamp) = self.timestamp {
+            txn = txn.with_timestamp_str(timestamp);
+        }
+
+        if self.log_request.is_some() || self.log_response.is_some() || self.log_code.is_some() {
+            // keep data that was sent with higher limits than the defaults
+            let mut log = Log::new().with_max_lengths(
+                self.log_request
+                    .as_ref()
+                    .map_or(0, String::len)
+                    .max(DEFAULT_MAX_REQUEST_LENGTH),
+                self.log_response
+                    .as_ref()
+                    .map_or(0, String::len)
+                    .max(DEFAULT_MAX_RESPONSE_LENGTH),
+            );
+
+            if let Some(request) = self.log_request {
+                log = log.with_request(request);
+            }
+            if let Some(response) = self.log_response {
+                log = log.with_response(response);
+            }
+            if let Some(code) = self.log_code {
+                log = log.with_code(code);
+            }
+
+            txn = txn.with_log(log);
+        }
+
+        if let Some(referrer) = self.referrer {
+            txn = txn.with_referrer(referrer);
+        }
+
+        if let Some(redirect) = self.redirect {
+            txn = txn.with_redirect(redirect);
+        }
+
+        Ok(txn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_call::Kind;
+
+    #[test]
+    fn splits_transaction_fields() {
+        assert_eq!(
+            Some((0, "user_key".to_owned())),
+            transaction_field("transactions[0]user_key")
+        );
+        assert_eq!(
+            Some((12, "usage[hits]".to_owned())),
+            transaction_field("transactions[12][usage][hits]")
+        );
+        assert_eq!(
+            Some((1, "log[code]".to_owned())),
+            transaction_field("transactions[1]log[code]")
+        );
+        assert_eq!(None, transaction_field("transactions[a]user_key"));
+        assert_eq!(None, transaction_field("user_key"));
+    }
+
+    #[test]
+    fn parses_report_body() {
+        let body = "service_id=a_service_id&provider_key=a_key\
+                    &transactions[0][app_id]=an_app_id&transactions[0][usage][hits]=1\
+                    &transactions[1][user_key]=a%20user+key&transactions[1][timestamp]=1";
+        let request = Request::from_parts(
+            Method::POST,
+            "/transactions.xml",
+            Some(body),
+            HeaderMap::new(),
+        )
+        .unwrap();
+        let apicall = ApiCall::try_from(&request).unwrap();
+
+        assert_eq!(Kind::Report, apicall.kind());
+        assert_eq!(
+            &Service::new("a_service_id", Credentials::from_key("a_key")),
+            apicall.service()
+        );
+
+        let txns = apicall.transactions();
+        assert_eq!(2, txns.len());
+        assert_eq!(
+            &Application::from_app_id("an_app_id"),
+            txns[0].application()
+        );
+        assert_eq!(
+            Some("hits"),
+            txns[0].usage().map(|u| u.as_vec()[0].metric())
+        );
+        assert_eq!(
+            &Application::from_user_key("a user key"),
+            txns[1].application()
+        );
+        assert_eq!(Some("1"), txns[1].timestamp());
+    }
+
+    #[test]
+    fn tells_apart_application_and_user_access_tokens() {
+        let request = Request::from_parts(
+            Method::GET,
+            "/transactions/oauth_authorize.xml?service_id=s&service_token=t\
+             &access_token=app_token&access_token=user_token",
+            None,
+            HeaderMap::new(),
+        )
+        .unwrap();
+        let apicall = ApiCall::try_from(&request).unwrap();
+
+        assert_eq!(
+            Some(&Application::from_oauth_token("app_token")),
+            apicall.application()
+        );
+        assert_eq!(Some(&User::from_oauth_token("user_token")), apicall.user());
+
+        let request = Request::from_parts(
+            Method::GET,
+            "/transactions/authorize.xml?service_id=s&service_token=t\
+             &user_key=a_user_key&access_token=user_token",
+            None,
+            HeaderMap::new(),
+        )
+        .unwrap();
+        let apicall = ApiCall::try_from(&request).unwrap();
+
+        assert_eq!(
+            Some(&Application::from_user_key("a_user_key")),
+            apicall.application()
+        );
+        assert_eq!(Some(&User::from_oauth_token("user_token")), apicall.user());
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        let parse = |method, uri, body| {
+            Request::from_parts(method, uri, body, HeaderMap::new())
+                .and_then(|r| ApiCall::try_from(&r))
+        };
+
+        // unknown endpoint
+        assert!(parse(Method::GET, "/transactions/unknown.xml", None).is_err());
+        // wrong method
+        assert!(parse(Method::POST, "/transactions/authorize.xml", None).is_err());
+        // missing application
+        assert!(parse(
+            Method::GET,
+            "/transactions/authorize.xml?service_id=s&service_token=t",
+            None
+        )
+        .is_err());
+        // unknown parameter
+        assert!(parse(
+            Method::GET,
+            "/transactions/authorize.xml?service_id=s&service_token=t&app_id=a&foo=bar",
+            None
+        )
+        .is_err());
+        // duplicate parameter
+        assert!(parse(
+            Method::GET,
+            "/transactions/authorize.xml?service_id=s&service_token=t&app_id=a&app_id=b",
+            None
+        )
+        .is_err());
+        // conflicting application credentials
+        assert!(parse(
+            Method::GET,
+            "/transactions/authorize.xml?service_id=s&service_token=t&app_id=a&user_key=b",
+            None
+        )
+        .is_err());
+        // non-indexed parameter in a report
+        assert!(parse(
+            Method::POST,
+            "/transactions.xml",
+            Some("service_id=s&service_token=t&user_key=b")
+        )
+        .is_err());
+    }
+}
@@ -13,7 +13,7 @@ macro_rules! reqwest_impl {
             fn setup_request(&mut self, r: Request, params: URI) -> Result<$B, Error> {
                 use core::convert::TryInto;
 
-                let (uri, body) = r.parameters.uri_and_body(r.path);
+                let (uri, body) = r.parameters.uri_and_body(&r.path);
                 let uri_base = params;
                 let uri = uri_base.to_string() + uri.as_ref();
 
@@ -36,3 +36,53 @@ macro_rules! reqwest_impl {
 reqwest_impl!(reqwest::Client, reqwest::RequestBuilder);
 #[cfg(feature = "reqwest-sync")]
 reqwest_impl!(reqwest::blocking::Client, reqwest::blocking::RequestBuilder);
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+mod response {
+    use std::prelude::v1::*;
+
+    use core::convert::TryFrom;
+
+    use crate::{
+        anyhow,
+        http::{
+            response::{typed_response, FromResponseParts, ReadResponse, ResponseError},
+            HeaderMap,
+        },
+    };
+
+    #[cfg(feature = "reqwest-async")]
+    use crate::http::response::ResponseFuture;
+
+    #[cfg(feature = "reqwest-async")]
+    impl<T: FromResponseParts + Send + 'static> ReadResponse<(), ResponseFuture<T>>
+        for reqwest::Response
+    {
+        fn read_response(self, _params: ()) -> ResponseFuture<T> {
+            Box::pin(async move {
+                let status = self.status().as_u16();
+                let headers = HeaderMap::try_from(self.headers());
+                let body = self.bytes().await.map_err(|e| {
+                    ResponseError::Transport(anyhow!("failed to read reqwest response body: {}", e))
+                })?;
+
+                typed_response(status, headers, body.as_ref())
+            })
+        }
+    }
+
+    #[cfg(feature = "reqwest-sync")]
+    impl<T: FromResponseParts> ReadResponse<(), Result<T, ResponseError>>
+        for reqwest::blocking::Response
+    {
+        fn read_response(self, _params: ()) -> Result<T, ResponseError> {
+            let status = self.status().as_u16();
+            let headers = HeaderMap::try_from(self.headers());
+            let body = self.bytes().map_err(|e| {
+                ResponseError::Transport(anyhow!("failed to read reqwest response body: {}", e))
+            })?;
+
+            typed_response(status, headers, body.as_ref())
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::prelude::v1::*;
+
+use super::{Request, SetupRequest};
+use crate::{anyhow, version::USER_AGENT, Error};
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+use crate::{
+    http::{
+        response::{typed_response, FromResponseParts, ReadResponse, ResponseError},
+        HeaderMap,
+    },
+    response::Authorization,
+};
+
+use ureq::{Agent, Response};
+
+/// A request ready to be sent by ureq.
+///
+/// ureq's requests don't hold a body, so it is kept here until the request is sent.
+#[derive(Debug, Clone)]
+pub struct UreqRequest {
+    request: ureq::Request,
+    body: Option<String>,
+}
+
+impl UreqRequest {
+    pub fn request(&self) -> &ureq::Request {
+        &self.request
+    }
+
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+
+    pub fn into_parts(self) -> (ureq::Request, Option<String>) {
+        (self.request, self.body)
+    }
+
+    /// Sends the request, along with its body if any.
+    ///
+    /// Note that ureq returns responses with 4xx and 5xx status codes as `ureq::Error::Status`.
+    #[allow(clippy::result_large_err)]
+    pub fn call(self) -> Result<Response, ureq::Error> {
+        match self.body {
+            Some(body) => self.request.send_string(body.as_str()),
+            None => self.request.call(),
+        }
+    }
+
+    /// Sends the request and parses the reply to an authorization, including the ones that
+    /// deny the call, which are sent with a 4xx status code.
+    #[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+    pub fn send_authorization(self) -> Result<Authorization, Error> {
+        let response = match self.call() {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(anyhow!("failed to send request with ureq: {}", e)),
+        };
+        let body = response
+            .into_string()
+            .map_err(|e| anyhow!("failed to read ureq response body: {}", e))?;
+
+        body.parse::<Authorization>()
+            .map_err(|e| anyhow!("failed to parse authorization: {}", e))
+    }
+}
+
+// ureq needs an absolute URI, so a base URI such as https://a_host is required.
+impl<URI: ToString> SetupRequest<'_, URI, Result<UreqRequest, Error>> for Agent {
+    fn setup_request(&mut self, r: Request, params: URI) -> Result<UreqRequest, Error> {
+        let (uri, body) = r.parameters.uri_and_body(&r.path);
+        let uri = params.to_string() + uri.as_ref();
+
+        let request = r.headers.iter().fold(
+            self.request(r.method.as_str(), uri.as_str()),
+            |rb, (k, v)| rb.set(k.as_str(), v.as_str()),
+        );
+        let request = match request.header("User-Agent") {
+            Some(_) => request,
+            None => request.set("User-Agent", USER_AGENT),
+        };
+
+        Ok(UreqRequest {
+            request,
+            body: match body {
+                // consume the body from the request's parameters to avoid cloning it
+                Some(_) => Some(r.parameters.into_inner()),
+                _ => None,
+            },
+        })
+    }
+}
+
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+impl<T: FromResponseParts> ReadResponse<(), Result<T, ResponseError>> for Response {
+    fn read_response(self, _params: ()) -> Result<T, ResponseError> {
+        use std::io::Read;
+
+        let status = self.status();
+        let headers = self
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = self.header(&name)?.to_owned();
+                Some((name, value))
+            })
+            .collect::<HeaderMap>();
+        let mut body = Vec::new();
+        self.into_reader().read_to_end(&mut body).map_err(|e| {
+            ResponseError::Transport(anyhow!("failed to read ureq response body: {}", e))
+        })?;
+
+        typed_response(status, Ok(headers), body.as_slice())
+    }
+}
+
+// ureq returns responses with 4xx and 5xx status codes as errors, which Apisonator uses to deny
+// authorizations and reject reports.
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+impl<T: FromResponseParts> ReadResponse<(), Result<T, ResponseError>>
+    for Result<Response, ureq::Error>
+{
+    fn read_response(self, params: ()) -> Result<T, ResponseError> {
+        match self {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response.read_response(params),
+            Err(e) => {
+                let connect = matches!(
+                    e.kind(),
+                    ureq::ErrorKind::Dns | ureq::ErrorKind::ConnectionFailed
+                );
+                let error = anyhow!("failed to send request with ureq: {}", e);
+
+                // only failures to connect are known not to have reached Apisonator
+                Err(if connect {
+                    ResponseError::Connect(error)
+                } else {
+                    ResponseError::Transport(error)
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api_call::*, application::*, credentials::*, service::*, transaction::*};
+
+    #[test]
+    fn sets_up_requests() {
+        let service = Service::new("my_service_id", Credentials::from_token("my_token"));
+        let app = Application::from_user_key("my_user_key");
+        let txn = Transaction::new(&app, None, None, None);
+        let txns = [txn.clone(), txn];
+        let apicall = ApiCall::builder(&service)
+            .report()
+            .transactions(&txns)
+            .unwrap()
+            .build();
+
+        let request = Agent::new()
+            .setup_request(Request::from(&apicall), "https://su1.3scale.net")
+            .unwrap();
+
+        assert_eq!(request.request().method(), "POST");
+        assert_eq!(
+            request.request().url(),
+            "https://su1.3scale.net/transactions.xml"
+        );
+        assert_eq!(request.request().header("User-Agent"), Some(USER_AGENT));
+        assert!(request
+            .body()
+            .unwrap()
+            .contains("transactions[1]user_key=my_user_key"));
+    }
+}
@@ -0,0 +1,220 @@
+use std::prelude::v1::*;
+
+use core::{fmt, future::Future, pin::Pin};
+
+use super::HeaderMap;
+use crate::{
+    response::{AuthorizationResponse, ReportResponse},
+    Error,
+};
+
+/// Typed responses that can be built out of the status code, headers and body sent by
+/// Apisonator.
+pub trait FromResponseParts: Sized {
+    fn from_response_parts(
+        status: u16,
+        headers: &HeaderMap,
+        body: Option<&str>,
+    ) -> Result<Self, Error>;
+
+    /// The HTTP status code the response was built with.
+    fn status_code(&self) -> u16;
+}
+
+impl FromResponseParts for AuthorizationResponse {
+    fn from_response_parts(
+        status: u16,
+        headers: &HeaderMap,
+        body: Option<&str>,
+    ) -> Result<Self, Error> {
+        Self::from_parts(status, headers, body)
+    }
+
+    fn status_code(&self) -> u16 {
+        self.status()
+    }
+}
+
+impl FromResponseParts for ReportResponse {
+    fn from_response_parts(
+        status: u16,
+        headers: &HeaderMap,
+        body: Option<&str>,
+    ) -> Result<Self, Error> {
+        Self::from_parts(status, headers, body)
+    }
+
+    fn status_code(&self) -> u16 {
+        self.status()
+    }
+}
+
+/// The error returned when reading a response fails.
+#[derive(Debug)]
+pub enum ResponseError {
+    /// The request could not be sent because no connection to Apisonator could be established,
+    /// so it never reached the server.
+    Connect(Error),
+    /// The response could not be received or read, ie. because of a network failure.
+    Transport(Error),
+    /// The response was received but is not a valid Apisonator response.
+    Protocol(Error),
+    /// The response has a 5xx status code and is not a valid Apisonator response, ie. because it
+    /// was sent by a proxy in front of Apisonator.
+    Server(u16, Error),
+}
+
+impl ResponseError {
+    pub fn is_connect(&self) -> bool {
+        matches!(self, ResponseError::Connect(_))
+    }
+
+    pub fn is_transport(&self) -> bool {
+        matches!(self, ResponseError::Transport(_))
+    }
+
+    pub fn is_protocol(&self) -> bool {
+        matches!(self, ResponseError::Protocol(_))
+    }
+
+    pub fn is_server(&self) -> bool {
+        self.status().is_some()
+    }
+
+    /// The status code of the response, for server errors.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ResponseError::Server(status, _) => Some(*status),
+            _ => None,
+        }
+    }
+
+    pub fn into_inner(self) -> Error {
+        match self {
+            ResponseError::Connect(e)
+            | ResponseError::Transport(e)
+            | ResponseError::Protocol(e)
+            | ResponseError::Server(_, e) => e,
+        }
+    }
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResponseError::Connect(e) => write!(f, "connection error: {}", e),
+            ResponseError::Transport(e) => write!(f, "transport error: {}", e),
+            ResponseError::Protocol(e) => write!(f, "protocol error: {}", e),
+            ResponseError::Server(status, e) => write!(f, "server error {}: {}", status, e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ResponseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResponseError::Connect(e)
+            | ResponseError::Transport(e)
+            | ResponseError::Protocol(e)
+            | ResponseError::Server(_, e) => Some(e.as_ref()),
+        }
+    }
+}
+
+/// The output of `ReadResponse` for asynchronous clients.
+pub type ResponseFuture<T> = Pin<Box<dyn Future<Output = Result<T, ResponseError>> + Send>>;
+
+/// This trait is the counterpart of `SetupRequest`, and is implemented by each client's response
+/// types to turn them into typed responses, such as `AuthorizationResponse` and `ReportResponse`.
+///
+/// Responses are consumed since their bodies usually can only be read once. Clients that don't
+/// have a response type of their own, like curl's, take the data they collected as parameters.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::{
+///     http::response::{ReadResponse, ResponseError},
+///     response::AuthorizationResponse,
+/// };
+///
+/// fn authorize<R>(response: R) -> Result<bool, ResponseError>
+/// where
+///     R: ReadResponse<(), Result<AuthorizationResponse, ResponseError>>,
+/// {
+///     let response = response.read_response(())?;
+///
+///     Ok(response.is_authorized())
+/// }
+/// ```
+pub trait ReadResponse<P, Output> {
+    fn read_response(self, params: P) -> Output;
+}
+
+/// Builds a typed response out of a client's response data, used by the client implementations.
+#[cfg(any(
+    test,
+    feature = "reqwest-sync",
+    feature = "reqwest-async",
+    feature = "curl-easy",
+    feature = "curl-easy2",
+    feature = "http-types",
+    feature = "ureq",
+    feature = "hyper"
+))]
+pub(crate) fn typed_response<T: FromResponseParts>(
+    status: u16,
+    headers: Result<HeaderMap, Error>,
+    body: &[u8],
+) -> Result<T, ResponseError> {
+    let headers = headers.map_err(ResponseError::Protocol)?;
+    let body = core::str::from_utf8(body).map_err(|e| {
+        ResponseError::Protocol(crate::anyhow!("response body is not valid UTF-8: {}", e))
+    })?;
+
+    T::from_response_parts(status, &headers, Some(body)).map_err(|e| match status {
+        500..=599 => ResponseError::Server(status, e),
+        _ => ResponseError::Protocol(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anyhow;
+
+    #[test]
+    fn classifies_errors() {
+        let error = typed_response::<AuthorizationResponse>(200, Ok(HeaderMap::new()), b"<status>")
+            .unwrap_err();
+        assert!(error.is_protocol());
+
+        let error =
+            typed_response::<ReportResponse>(202, Ok(HeaderMap::new()), b"\xff").unwrap_err();
+        assert!(error.is_protocol());
+
+        let error = typed_response::<AuthorizationResponse>(503, Ok(HeaderMap::new()), b"<html>")
+            .unwrap_err();
+        assert!(error.is_server());
+        assert_eq!(error.status(), Some(503));
+
+        let error = ResponseError::Transport(anyhow!("connection reset"));
+        assert!(error.is_transport());
+        assert_eq!(error.to_string(), "transport error: connection reset");
+    }
+
+    #[test]
+    fn reads_typed_responses() {
+        let response = typed_response::<AuthorizationResponse>(
+            200,
+            Ok(HeaderMap::new()),
+            b"<status><authorized>true</authorized><plan>Basic</plan></status>",
+        )
+        .unwrap();
+        assert!(response.is_authorized());
+
+        let response = typed_response::<ReportResponse>(202, Ok(HeaderMap::new()), b"").unwrap();
+        assert!(response.is_accepted());
+    }
+}
@@ -21,15 +21,19 @@ pub mod credentials;
 pub mod encoding;
 pub mod extensions;
 pub mod http;
+pub mod log;
 pub mod service;
 pub mod transaction;
 pub mod usage;
 pub mod user;
 pub mod version;
 
-#[cfg(feature = "xml-response")]
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
 pub mod response;
 
+#[cfg(any(feature = "xml-response", feature = "xml-response-lite"))]
+pub mod client;
+
 pub(crate) mod error {
     pub use anyhow::{anyhow, Error, Result};
 }
@@ -39,6 +43,9 @@ pub use error::Error;
 #[allow(unused_imports)]
 pub(crate) use error::anyhow;
 
+#[cfg(test)]
+mod fixtures;
+
 use std::borrow::Cow;
 
 /// This is the trait to be implemented by structures that can set parameters to API calls.
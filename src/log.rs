@@ -0,0 +1,201 @@
+use std::prelude::v1::*;
+
+use std::borrow::Cow;
+
+use crate::ToParams;
+
+/// Default maximum length in bytes of the request sent as part of a log.
+pub const DEFAULT_MAX_REQUEST_LENGTH: usize = 1024;
+/// Default maximum length in bytes of the response sent as part of a log.
+pub const DEFAULT_MAX_RESPONSE_LENGTH: usize = 4096;
+
+/// Traffic log data for a transaction, used to fill in the 3scale traffic log.
+///
+/// Request and response data are truncated to a maximum length when sent so that big payloads
+/// don't end up making requests too large.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log<'a> {
+    request: Option<Cow<'a, str>>,
+    response: Option<Cow<'a, str>>,
+    code: Option<String>,
+    max_request_length: usize,
+    max_response_length: usize,
+}
+
+impl Default for Log<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Log<'a> {
+    /// Creates an empty `Log`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::log::*;
+    ///
+    /// let log = Log::new()
+    ///     .with_request("GET /products HTTP/1.1")
+    ///     .with_response("[]")
+    ///     .with_code(200);
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            request: None,
+            response: None,
+            code: None,
+            max_request_length: DEFAULT_MAX_REQUEST_LENGTH,
+            max_response_length: DEFAULT_MAX_RESPONSE_LENGTH,
+        }
+    }
+
+    pub fn with_request<S: Into<Cow<'a, str>>>(mut self, request: S) -> Self {
+        self.request = Some(request.into());
+        self
+    }
+
+    pub fn with_response<S: Into<Cow<'a, str>>>(mut self, response: S) -> Self {
+        self.response = Some(response.into());
+        self
+    }
+
+    pub fn with_code(mut self, code: u16) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    /// Sets the maximum lengths in bytes of the request and response data to be sent.
+    pub fn with_max_lengths(
+        mut self,
+        max_request_length: usize,
+        max_response_length: usize,
+    ) -> Self {
+        self.max_request_length = max_request_length;
+        self.max_response_length = max_response_length;
+        self
+    }
+
+    /// The request data, truncated to the maximum request length.
+    pub fn request(&self) -> Option<&str> {
+        self.request
+            .as_deref()
+            .map(|r| truncate(r, self.max_request_length))
+    }
+
+    /// The response data, truncated to the maximum response length.
+    pub fn response(&self) -> Option<&str> {
+        self.response
+            .as_deref()
+            .map(|r| truncate(r, self.max_response_length))
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.request.is_none() && self.response.is_none() && self.code.is_none()
+    }
+
+    pub fn into_owned(self) -> Log<'static> {
+        Log {
+            request: self.request.map(|r| r.into_owned().into()),
+            response: self.response.map(|r| r.into_owned().into()),
+            code: self.code,
+            max_request_length: self.max_request_length,
+            max_response_length: self.max_response_length,
+        }
+    }
+}
+
+// Truncates a string to at most max bytes without splitting characters.
+fn truncate(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+
+    let mut idx = max;
+    while !s.is_char_boundary(idx) {
+        idx -= 1;
+    }
+
+    &s[..idx]
+}
+
+impl<'k, 'v, 'this, E> ToParams<'k, 'v, 'this, E> for Log<'_>
+where
+    'this: 'k + 'v,
+    E: Extend<(Cow<'k, str>, &'v str)>,
+{
+    fn to_params_with_mangling<F: FnMut(Cow<'k, str>) -> Cow<'k, str>>(
+        &'this self,
+        extendable: &mut E,
+        key_mangling: &mut F,
+    ) {
+        let params = [
+            ("log[request]", self.request()),
+            ("log[response]", self.response()),
+            ("log[code]", self.code()),
+        ];
+
+        extendable.extend(
+            params
+                .iter()
+                .filter_map(|&(k, v)| v.map(|v| (key_mangling(k.into()), v))),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms_log_into_params() {
+        let log = Log::new()
+            .with_request("GET /")
+            .with_response("OK")
+            .with_code(200);
+
+        let mut result = Vec::new();
+        log.to_params(&mut result);
+
+        let expected: Vec<(Cow<str>, &str)> = vec![
+            ("log[request]".into(), "GET /"),
+            ("log[response]".into(), "OK"),
+            ("log[code]".into(), "200"),
+        ];
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn skips_missing_fields() {
+        let log = Log::new().with_code(404);
+
+        let mut result = Vec::new();
+        log.to_params(&mut result);
+
+        let expected: Vec<(Cow<str>, &str)> = vec![("log[code]".into(), "404")];
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn truncates_request_and_response() {
+        let response = "x".repeat(DEFAULT_MAX_RESPONSE_LENGTH + 1);
+        let log = Log::new()
+            .with_request("GET /résumé")
+            .with_response(response.as_str());
+
+        assert_eq!(
+            Some(&response[..DEFAULT_MAX_RESPONSE_LENGTH]),
+            log.response()
+        );
+
+        // 'é' takes two bytes, so it can't be split in the middle
+        let log = log.with_max_lengths(7, 3);
+        assert_eq!(Some("GET /r"), log.request());
+        assert_eq!(Some("xxx"), log.response());
+    }
+}
@@ -2,25 +2,52 @@ use std::prelude::v1::*;
 
 use std::{fmt, str::FromStr};
 
-use chrono::prelude::*;
-use serde::{
-    de::{self, Deserializer, MapAccess, Visitor},
-    Deserialize,
-};
+use crate::{anyhow, http::HeaderMap, Error};
+
+#[cfg(feature = "xml-response")]
+use serde::Deserialize;
+
+mod analytics;
 
 mod app_keys_list;
 pub use app_keys_list::AppKeysList;
 
+mod error_code;
+pub use error_code::{ErrorCategory, ErrorCode};
+
+mod headers;
+pub use headers::{
+    ResponseHeaders, LIMIT_MAX_VALUE_HEADER, LIMIT_REMAINING_HEADER, LIMIT_RESET_HEADER,
+    REJECTION_REASON_HEADER,
+};
+
+mod report;
+pub use report::{ReportError, ReportResponse};
+
 mod metrics_hierarchy;
 pub use metrics_hierarchy::MetricsHierarchy;
 
+mod oauth_application;
+pub use oauth_application::OAuthApplication;
+
+mod timestamp;
+
+pub mod gateway;
+
+pub mod xml;
+
+#[cfg(feature = "json-response")]
+pub mod json;
+
 mod systemtime {
+    #[cfg(feature = "xml-response")]
     use chrono::DateTime;
 
     #[repr(transparent)]
     #[derive(Debug, Clone, PartialEq, Eq)]
     pub struct PeriodTime(pub i64);
 
+    #[cfg(feature = "xml-response")]
     impl<Tz: chrono::TimeZone> From<DateTime<Tz>> for PeriodTime {
         fn from(dt: DateTime<Tz>) -> PeriodTime {
             PeriodTime(dt.timestamp())
@@ -30,8 +57,9 @@ mod systemtime {
 
 pub use systemtime::PeriodTime;
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
-#[serde(rename = "usage_report")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
+#[cfg_attr(feature = "xml-response", serde(rename = "usage_report"))]
 pub struct UsageReport {
     pub metric: String,
     pub period: Period,
@@ -44,31 +72,37 @@ pub struct UsageReport {
 // Unfortunately the XML output from Apisonator includes a rather useless "usage_reports" tag that
 // is then followed by a "usage_report" tag in each UsageReport, so we need to wrap that up.
 #[cfg_attr(supports_transparent_enums, repr(transparent))]
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
 pub enum UsageReports {
-    #[serde(rename = "usage_report")]
+    #[cfg_attr(feature = "xml-response", serde(rename = "usage_report"))]
     UsageReports(Vec<UsageReport>),
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
+#[cfg_attr(feature = "xml-response", serde(rename_all = "snake_case"))]
 pub enum Authorization {
     Status(AuthorizationStatus),
     Error(AuthorizationError),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
 pub struct AuthorizationStatus {
     authorized: bool,
     reason: Option<String>,
     plan: String,
     usage_reports: Option<UsageReports>,
 
-    #[serde(rename = "hierarchy")]
+    #[cfg_attr(feature = "xml-response", serde(rename = "hierarchy"))]
     metrics_hierarchy: Option<MetricsHierarchy>,
 
-    #[serde(rename = "app_keys")]
+    #[cfg_attr(feature = "xml-response", serde(rename = "app_keys"))]
     app_keys: Option<AppKeysList>,
+
+    // only returned by the OAuth endpoints, boxed to keep the size of the common case down
+    application: Option<Box<OAuthApplication>>,
 }
 
 impl AuthorizationStatus {
@@ -95,17 +129,120 @@ impl AuthorizationStatus {
     pub fn hierarchy(&self) -> Option<&MetricsHierarchy> {
         self.metrics_hierarchy.as_ref()
     }
+
+    /// The application data returned by the OAuth endpoints.
+    pub fn application(&self) -> Option<&OAuthApplication> {
+        self.application.as_deref()
+    }
 }
 
-#[repr(transparent)]
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
 pub struct AuthorizationError {
-    code: String,
+    code: ErrorCode,
+
+    #[cfg_attr(feature = "xml-response", serde(rename = "$value"))]
+    message: Option<String>,
 }
 
 impl AuthorizationError {
     pub fn code(&self) -> &str {
-        self.code.as_ref()
+        self.code.as_str()
+    }
+
+    /// The human-readable description of the error.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    pub fn error_code(&self) -> &ErrorCode {
+        &self.code
+    }
+
+    /// The category of the error, or `None` if the code is unknown.
+    pub fn category(&self) -> Option<ErrorCategory> {
+        self.code.category()
+    }
+}
+
+/// The response to an authorization, combining the information found in the body, if any, with
+/// the one found in response headers.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::{http::HeaderMap, response::*};
+///
+/// // a response to a call using the no_body and limit_headers extensions
+/// let headers = vec![("3scale-limit-remaining", "9"), ("3scale-limit-reset", "30")]
+///     .into_iter()
+///     .collect::<HeaderMap>();
+///
+/// let response = AuthorizationResponse::from_parts(200, &headers, None).unwrap();
+///
+/// assert!(response.is_authorized());
+/// assert!(response.authorization().is_none());
+/// assert_eq!(response.headers().limit_remaining(), Some(9));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizationResponse {
+    status: u16,
+    authorization: Option<Authorization>,
+    headers: ResponseHeaders,
+}
+
+impl AuthorizationResponse {
+    /// Builds the response out of the HTTP status code, headers and body sent by Apisonator. An
+    /// empty body is expected when using the `no_body` extension.
+    pub fn from_parts(status: u16, headers: &HeaderMap, body: Option<&str>) -> Result<Self, Error> {
+        let authorization = match body.map(str::trim) {
+            Some(body) if !body.is_empty() => Some(
+                parse_authorization(body)
+                    .map_err(|e| anyhow!("failed to parse authorization: {}", e))?,
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            status,
+            authorization,
+            headers: ResponseHeaders::from_header_map(headers)?,
+        })
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn authorization(&self) -> Option<&Authorization> {
+        self.authorization.as_ref()
+    }
+
+    pub fn headers(&self) -> &ResponseHeaders {
+        &self.headers
+    }
+
+    /// Whether the call was authorized, using the status code when there is no body.
+    pub fn is_authorized(&self) -> bool {
+        match &self.authorization {
+            Some(Authorization::Status(status)) => status.authorized(),
+            Some(Authorization::Error(_)) => false,
+            None => self.status == 200,
+        }
+    }
+
+    /// The reason for a rejection, either from the rejection reason header or the error code.
+    pub fn rejection_reason(&self) -> Option<&ErrorCode> {
+        if self.is_authorized() {
+            return None;
+        }
+
+        self.headers
+            .rejection_reason()
+            .or_else(|| match &self.authorization {
+                Some(Authorization::Error(e)) => Some(e.error_code()),
+                _ => None,
+            })
     }
 }
 
@@ -120,20 +257,31 @@ pub enum Period {
     Eternity,
 }
 
-struct PeriodStringVisitor;
-
-impl<'de> Visitor<'de> for PeriodStringVisitor {
-    type Value = Period;
+impl Period {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Minute => "minute",
+            Period::Hour => "hour",
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Year => "year",
+            Period::Eternity => "eternity",
+        }
+    }
+}
 
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a string that represents a period")
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
+}
+
+impl FromStr for Period {
+    type Err = Error;
 
-    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        match v {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
             "minute" => Ok(Period::Minute),
             "hour" => Ok(Period::Hour),
             "day" => Ok(Period::Day),
@@ -141,71 +289,103 @@ impl<'de> Visitor<'de> for PeriodStringVisitor {
             "month" => Ok(Period::Month),
             "year" => Ok(Period::Year),
             "eternity" => Ok(Period::Eternity),
-            _ => Err(E::custom("Invalid period")),
+            _ => Err(anyhow!("invalid period {}", s)),
         }
     }
 }
 
-impl<'de> Deserialize<'de> for Period {
-    fn deserialize<D>(deserializer: D) -> Result<Period, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        deserializer.deserialize_any(PeriodStringVisitor)
-    }
-}
+#[cfg(feature = "xml-response")]
+mod de {
+    use std::prelude::v1::*;
+
+    use std::fmt;
+
+    use chrono::prelude::*;
+    use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+
+    use super::{Period, PeriodTime};
 
-struct TimestampVisitor;
+    struct PeriodStringVisitor;
 
-impl<'de> Visitor<'de> for TimestampVisitor {
-    type Value = PeriodTime;
+    impl<'de> Visitor<'de> for PeriodStringVisitor {
+        type Value = Period;
 
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a string that represents a timestamp")
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a string that represents a period")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            v.parse().map_err(|_| E::custom("Invalid period"))
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Period {
+        fn deserialize<D>(deserializer: D) -> Result<Period, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(PeriodStringVisitor)
+        }
     }
 
-    fn visit_map<V>(self, mut map: V) -> Result<PeriodTime, V::Error>
-    where
-        V: MapAccess<'de>,
-    {
-        // We know there's only one key with one value.
-        // The key is not used, but we need to call "next_key()". From the
-        // docs: "Calling `next_value` before `next_key` is incorrect and is
-        // allowed to panic or return bogus results.".
-        let _key: Option<String> = map.next_key()?;
-        let timestamp: String = map.next_value()?;
+    struct TimestampVisitor;
 
-        let ts_str = timestamp.as_str();
-        let dt = DateTime::parse_from_str(ts_str, "%Y-%m-%d %H:%M:%S %z").map_err(|e| {
-            de::Error::custom(format_args!(
-                "invalid timestamp {}, expected %Y-%m-%d %H:%M:%S %z: {:?}",
-                ts_str, e
-            ))
-        })?;
+    impl<'de> Visitor<'de> for TimestampVisitor {
+        type Value = PeriodTime;
 
-        Ok(PeriodTime::from(dt))
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a string that represents a timestamp")
+        }
+
+        fn visit_map<V>(self, mut map: V) -> Result<PeriodTime, V::Error>
+        where
+            V: MapAccess<'de>,
+        {
+            // We know there's only one key with one value.
+            // The key is not used, but we need to call "next_key()". From the
+            // docs: "Calling `next_value` before `next_key` is incorrect and is
+            // allowed to panic or return bogus results.".
+            let _key: Option<String> = map.next_key()?;
+            let timestamp: String = map.next_value()?;
+
+            let ts_str = timestamp.as_str();
+            let dt = DateTime::parse_from_str(ts_str, "%Y-%m-%d %H:%M:%S %z").map_err(|e| {
+                de::Error::custom(format_args!(
+                    "invalid timestamp {}, expected %Y-%m-%d %H:%M:%S %z: {:?}",
+                    ts_str, e
+                ))
+            })?;
+
+            Ok(PeriodTime::from(dt))
+        }
     }
-}
 
-impl<'de> Deserialize<'de> for PeriodTime {
-    fn deserialize<D>(deserializer: D) -> Result<PeriodTime, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        deserializer.deserialize_any(TimestampVisitor)
+    impl<'de> Deserialize<'de> for PeriodTime {
+        fn deserialize<D>(deserializer: D) -> Result<PeriodTime, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(TimestampVisitor)
+        }
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
 pub struct UsageData {
     max_value: u64,
     current_value: u64,
 }
 
 #[repr(transparent)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
 pub struct Metric(pub String);
 
+#[cfg(feature = "xml-response")]
 impl FromStr for Authorization {
     type Err = serde_xml_rs::Error;
 
@@ -214,9 +394,37 @@ impl FromStr for Authorization {
     }
 }
 
-#[cfg(test)]
+#[cfg(not(feature = "xml-response"))]
+impl FromStr for Authorization {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        xml::parse_authorization(s)
+    }
+}
+
+// The built-in parser is preferred when available, since it is faster than serde-xml-rs.
+#[cfg(feature = "xml-response-lite")]
+fn parse_authorization(body: &str) -> Result<Authorization, Error> {
+    xml::parse_authorization(body)
+}
+
+#[cfg(not(feature = "xml-response-lite"))]
+fn parse_authorization(body: &str) -> Result<Authorization, Error> {
+    Authorization::from_str(body).map_err(|e| anyhow!("{}", e))
+}
+
+#[cfg(all(test, feature = "xml-response"))]
 mod tests {
     use super::{UsageReports::*, *};
+    use chrono::prelude::*;
+
+    // Checks that serializing a parsed authorization yields the same authorization.
+    fn assert_roundtrip(authorization: &Authorization) {
+        let xml = xml::to_string(authorization);
+
+        assert_eq!(authorization, &Authorization::from_str(&xml).unwrap());
+    }
 
     #[test]
     fn parse() {
@@ -244,12 +452,15 @@ mod tests {
 
         let parsed_auth = Authorization::from_str(s).unwrap();
 
+        assert_roundtrip(&parsed_auth);
+
         let expected_auth = Authorization::Status(AuthorizationStatus {
             authorized: true,
             reason: None,
             plan: String::from("App Plan"),
             metrics_hierarchy: None,
             app_keys: None,
+            application: None,
             usage_reports: Some(UsageReports(vec![
                 UsageReport {
                     metric: String::from("products"),
@@ -321,9 +532,11 @@ mod tests {
             usage_reports: None,
             metrics_hierarchy: None,
             app_keys: None,
+            application: None,
         });
         let parsed_auth = Authorization::from_str(s)
             .expect("failed to parse authorization without usage reports");
+        assert_roundtrip(&parsed_auth);
 
         assert_eq!(expected_auth, parsed_auth);
     }
@@ -337,12 +550,141 @@ mod tests {
 
         let parsed_auth = Authorization::from_str(xml_response).unwrap();
 
+        assert_roundtrip(&parsed_auth);
+
+        let expected_auth = Authorization::Error(AuthorizationError {
+            code: ErrorCode::UserKeyInvalid,
+            message: Some(String::from(r#"user key "some_user_key" is invalid"#)),
+        });
+        assert_eq!(parsed_auth, expected_auth);
+    }
+
+    #[test]
+    fn parse_error_message_with_entities() {
+        let xml_response = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <error code="metric_invalid">metric &quot;a&lt;b&gt; &amp; c&quot; is invalid</error>
+        "##;
+
+        let parsed_auth = Authorization::from_str(xml_response).unwrap();
+
+        assert_roundtrip(&parsed_auth);
+
+        let expected_auth = Authorization::Error(AuthorizationError {
+            code: ErrorCode::MetricInvalid,
+            message: Some(String::from(r#"metric "a<b> & c" is invalid"#)),
+        });
+        assert_eq!(parsed_auth, expected_auth);
+    }
+
+    #[test]
+    fn parse_error_message_with_cdata() {
+        let xml_response = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <error code="application_not_found"><![CDATA[application with id="<a&b>" was not found]]></error>
+        "##;
+
+        let parsed_auth = Authorization::from_str(xml_response).unwrap();
+
+        assert_roundtrip(&parsed_auth);
+
+        let expected_auth = Authorization::Error(AuthorizationError {
+            code: ErrorCode::ApplicationNotFound,
+            message: Some(String::from(r#"application with id="<a&b>" was not found"#)),
+        });
+        assert_eq!(parsed_auth, expected_auth);
+    }
+
+    #[test]
+    fn parse_error_without_message() {
+        let xml_response = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <error code="limits_exceeded"/>
+        "##;
+
+        let parsed_auth = Authorization::from_str(xml_response).unwrap();
+
+        assert_roundtrip(&parsed_auth);
+
         let expected_auth = Authorization::Error(AuthorizationError {
-            code: String::from("user_key_invalid"),
+            code: ErrorCode::LimitsExceeded,
+            message: None,
         });
         assert_eq!(parsed_auth, expected_auth);
     }
 
+    #[test]
+    fn authorization_response_combines_body_and_headers() {
+        let body = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <status>
+            <authorized>false</authorized>
+            <reason>usage limits are exceeded</reason>
+            <plan>Basic</plan>
+        </status>
+        "##;
+        let headers = vec![
+            ("3scale-limit-remaining", "0"),
+            ("3scale-limit-reset", "10"),
+            ("3scale-rejection-reason", "limits_exceeded"),
+        ]
+        .into_iter()
+        .collect::<HeaderMap>();
+
+        let response = AuthorizationResponse::from_parts(409, &headers, Some(body)).unwrap();
+
+        assert!(!response.is_authorized());
+        assert_eq!(
+            Some(&ErrorCode::LimitsExceeded),
+            response.rejection_reason()
+        );
+        assert_eq!(Some(0), response.headers().limit_remaining());
+        assert!(matches!(
+            response.authorization(),
+            Some(Authorization::Status(_))
+        ));
+
+        let response = AuthorizationResponse::from_parts(409, &HeaderMap::new(), Some("")).unwrap();
+
+        assert!(!response.is_authorized());
+        assert_eq!(None, response.rejection_reason());
+    }
+
+    #[test]
+    fn authorization_response_takes_rejection_reason_from_errors() {
+        let body = r##"<error code="user_key_invalid">user key "a" is invalid</error>"##;
+
+        let response =
+            AuthorizationResponse::from_parts(403, &HeaderMap::new(), Some(body)).unwrap();
+
+        assert!(!response.is_authorized());
+        assert_eq!(
+            Some(&ErrorCode::UserKeyInvalid),
+            response.rejection_reason()
+        );
+    }
+
+    #[test]
+    fn parse_unknown_error_code() {
+        let xml_response = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <error code="some_new_error">something went wrong</error>
+        "##;
+
+        let parsed_auth = Authorization::from_str(xml_response).unwrap();
+
+        assert_roundtrip(&parsed_auth);
+
+        match parsed_auth {
+            Authorization::Error(e) => {
+                assert_eq!(&ErrorCode::Unknown("some_new_error".into()), e.error_code());
+                assert_eq!("some_new_error", e.code());
+                assert_eq!(None, e.category());
+            }
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     fn parse_denied_authorization() {
         let xml_response = r##"
@@ -364,6 +706,8 @@ mod tests {
 
         let parsed_auth = Authorization::from_str(xml_response).unwrap();
 
+        assert_roundtrip(&parsed_auth);
+
         let expected_auth = Authorization::Status(AuthorizationStatus {
             authorized: false,
             reason: Some("application key is missing".into()),
@@ -378,6 +722,7 @@ mod tests {
             }])),
             metrics_hierarchy: None,
             app_keys: None,
+            application: None,
         });
         assert_eq!(expected_auth, parsed_auth);
     }
@@ -430,6 +775,8 @@ mod tests {
 
         let parsed_auth = Authorization::from_str(xml_response).unwrap();
 
+        assert_roundtrip(&parsed_auth);
+
         let mut expected_hierarchy = MetricsHierarchy::new();
         expected_hierarchy.insert(
             "parent1",
@@ -443,6 +790,7 @@ mod tests {
             plan: String::from("Basic"),
             metrics_hierarchy: Some(expected_hierarchy),
             app_keys: None,
+            application: None,
             usage_reports: Some(UsageReports(vec![
                 UsageReport {
                     metric: String::from("parent1"),
@@ -542,6 +890,8 @@ mod tests {
 
         let parsed_auth = Authorization::from_str(xml_response).unwrap();
 
+        assert_roundtrip(&parsed_auth);
+
         let expected_app_keys = AppKeysList::new(
             "service_id".into(),
             "app_id".into(),
@@ -553,6 +903,7 @@ mod tests {
             reason: None,
             plan: String::from("Basic"),
             app_keys: Some(expected_app_keys),
+            application: None,
             metrics_hierarchy: None,
             usage_reports: None,
         });
@@ -560,6 +911,70 @@ mod tests {
         assert_eq!(parsed_auth, expected_auth);
     }
 
+    #[test]
+    fn parse_oauth_application() {
+        let xml_response = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <status>
+            <authorized>true</authorized>
+            <application>
+                <id>94bd2de3</id>
+                <key>883bdb8dbc3b6b77dbcf26845560fdbb</key>
+                <redirect_url>http://example.com/callback?a=1&amp;b=2</redirect_url>
+            </application>
+            <plan>Basic</plan>
+        </status>
+        "##;
+
+        let parsed_auth = Authorization::from_str(xml_response).unwrap();
+
+        assert_roundtrip(&parsed_auth);
+
+        let expected_auth = Authorization::Status(AuthorizationStatus {
+            authorized: true,
+            reason: None,
+            plan: String::from("Basic"),
+            app_keys: None,
+            application: Some(Box::new(OAuthApplication::new(
+                "94bd2de3",
+                Some("883bdb8dbc3b6b77dbcf26845560fdbb"),
+                Some("http://example.com/callback?a=1&b=2"),
+            ))),
+            metrics_hierarchy: None,
+            usage_reports: None,
+        });
+
+        assert_eq!(parsed_auth, expected_auth);
+    }
+
+    #[test]
+    fn parse_oauth_application_without_key_and_redirect_url() {
+        let xml_response = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <status>
+            <authorized>true</authorized>
+            <application>
+                <id>94bd2de3</id>
+                <key></key>
+                <redirect_url/>
+            </application>
+            <plan>Basic</plan>
+        </status>
+        "##;
+
+        let parsed_auth = Authorization::from_str(xml_response).unwrap();
+
+        assert_roundtrip(&parsed_auth);
+
+        let application = match &parsed_auth {
+            Authorization::Status(status) => status.application().unwrap(),
+            _ => unreachable!(),
+        };
+        assert_eq!("94bd2de3", application.id());
+        assert_eq!(None, application.key());
+        assert_eq!(None, application.redirect_url());
+    }
+
     #[test]
     fn parse_empty_app_keys() {
         let xml_response = r##"
@@ -574,6 +989,8 @@ mod tests {
 
         let parsed_auth = Authorization::from_str(xml_response).unwrap();
 
+        assert_roundtrip(&parsed_auth);
+
         let expected_app_keys = AppKeysList::new(
             "service_id".into(),
             "app_id".into(),
@@ -585,6 +1002,7 @@ mod tests {
             reason: None,
             plan: String::from("Basic"),
             app_keys: Some(expected_app_keys),
+            application: None,
             metrics_hierarchy: None,
             usage_reports: None,
         });
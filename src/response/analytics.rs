@@ -0,0 +1,318 @@
+use std::prelude::v1::*;
+
+use super::{AuthorizationStatus, Period, PeriodTime, UsageReport, UsageReports};
+
+/// The reason Apisonator gives for denials because of limits.
+pub(super) const LIMITS_EXCEEDED_REASON: &str = "usage limits are exceeded";
+
+impl UsageReport {
+    /// The units that can still be used before reaching the limit.
+    pub fn remaining(&self) -> u64 {
+        self.max_value.saturating_sub(self.current_value)
+    }
+
+    /// Whether the metric is disabled, ie. its limit is 0.
+    pub fn is_disabled(&self) -> bool {
+        self.max_value == 0
+    }
+
+    /// Whether the limit has been reached.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Whether the limit never gets reset, either because it is disabled or because its period
+    /// is `Period::Eternity`.
+    pub fn never_resets(&self) -> bool {
+        self.is_disabled() || self.period == Period::Eternity
+    }
+
+    /// The number of seconds from `now` until the current period ends, or `None` if the limit
+    /// never gets reset.
+    pub fn seconds_until_reset(&self, now: &PeriodTime) -> Option<u64> {
+        if self.never_resets() {
+            return None;
+        }
+
+        let seconds = self.period_end.0.saturating_sub(now.0);
+
+        Some(if seconds > 0 { seconds as u64 } else { 0 })
+    }
+
+    // Orders reports by how constraining they are: less remaining units first, and then the ones
+    // taking longer to reset.
+    fn tightness_key(&self) -> (u64, bool, i64) {
+        (
+            self.remaining(),
+            !self.never_resets(),
+            self.period_end.0.wrapping_neg(),
+        )
+    }
+}
+
+impl UsageReports {
+    pub fn reports(&self) -> &[UsageReport] {
+        match self {
+            UsageReports::UsageReports(reports) => reports.as_slice(),
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, UsageReport> {
+        self.reports().iter()
+    }
+
+    /// The reports for a given metric, one for each period with a limit.
+    pub fn for_metric<'a>(&'a self, metric: &'a str) -> impl Iterator<Item = &'a UsageReport> {
+        self.iter().filter(move |r| r.metric == metric)
+    }
+
+    /// The reports whose limits have been reached.
+    pub fn exhausted(&self) -> impl Iterator<Item = &UsageReport> {
+        self.iter().filter(|r| r.is_exhausted())
+    }
+
+    /// The most constraining report, ie. the one with the least remaining units, preferring the
+    /// ones taking longer to reset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::response::*;
+    ///
+    /// let report = |period, max_value, current_value| UsageReport {
+    ///     metric: "hits".into(),
+    ///     period,
+    ///     period_start: PeriodTime(0),
+    ///     period_end: PeriodTime(3600),
+    ///     max_value,
+    ///     current_value,
+    /// };
+    /// let reports = UsageReports::UsageReports(vec![
+    ///     report(Period::Minute, 10, 5),
+    ///     report(Period::Hour, 100, 98),
+    /// ]);
+    ///
+    /// let tightest = reports.tightest().unwrap();
+    ///
+    /// assert_eq!(tightest.period, Period::Hour);
+    /// assert_eq!(tightest.remaining(), 2);
+    /// ```
+    pub fn tightest(&self) -> Option<&UsageReport> {
+        self.iter().min_by_key(|r| r.tightness_key())
+    }
+
+    /// The most constraining report for a given metric.
+    pub fn tightest_for_metric(&self, metric: &str) -> Option<&UsageReport> {
+        self.iter()
+            .filter(|r| r.metric == metric)
+            .min_by_key(|r| r.tightness_key())
+    }
+
+    /// The units of a metric that can still be used across all of its periods.
+    pub fn remaining_for_metric(&self, metric: &str) -> Option<u64> {
+        self.tightest_for_metric(metric).map(UsageReport::remaining)
+    }
+}
+
+impl AuthorizationStatus {
+    /// Whether the call was denied because of limits.
+    ///
+    /// Denials for other reasons can include reports of disabled metrics or of metrics at their
+    /// limit, so usage reports are only looked at when there is no reason.
+    pub fn is_limits_exceeded(&self) -> bool {
+        if self.authorized {
+            return false;
+        }
+
+        match self.reason() {
+            Some(reason) => reason == LIMITS_EXCEEDED_REASON,
+            None => self
+                .usage_reports
+                .iter()
+                .flat_map(|reports| reports.iter())
+                .any(|r| r.max_value > 0 && r.current_value > r.max_value),
+        }
+    }
+
+    /// The report of the limit that caused a denial, if the call was denied because of limits.
+    ///
+    /// When several limits were reached, the one taking longer to reset is returned. When none
+    /// was reached yet, ie. the usage of the call would have exceeded a limit, the tightest limit
+    /// is returned.
+    pub fn denial_cause(&self) -> Option<&UsageReport> {
+        if !self.is_limits_exceeded() {
+            return None;
+        }
+
+        let reports = self.usage_reports.as_ref()?;
+
+        reports
+            .exhausted()
+            .max_by_key(|r| (r.never_resets(), r.period_end.0))
+            .or_else(|| reports.tightest())
+    }
+
+    /// The number of seconds from `now` after which a call denied because of limits could be
+    /// authorized, or `None` if it was not denied because of limits or it never will be.
+    pub fn retry_after(&self, now: &PeriodTime) -> Option<u64> {
+        self.denial_cause()?.seconds_until_reset(now)
+    }
+
+    /// The most constraining limit across all metrics and periods.
+    pub fn tightest_limit(&self) -> Option<&UsageReport> {
+        self.usage_reports.as_ref()?.tightest()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(metric: &str, period: Period, end: i64, max: u64, current: u64) -> UsageReport {
+        UsageReport {
+            metric: metric.into(),
+            period,
+            period_start: PeriodTime(0),
+            period_end: PeriodTime(end),
+            max_value: max,
+            current_value: current,
+        }
+    }
+
+    fn status(authorized: bool, reports: Vec<UsageReport>) -> AuthorizationStatus {
+        let reason = if authorized {
+            None
+        } else {
+            Some(LIMITS_EXCEEDED_REASON.into())
+        };
+
+        AuthorizationStatus {
+            authorized,
+            reason,
+            plan: "Basic".into(),
+            usage_reports: Some(UsageReports::UsageReports(reports)),
+            metrics_hierarchy: None,
+            app_keys: None,
+            application: None,
+        }
+    }
+
+    #[test]
+    fn computes_remaining_and_reset() {
+        let r = report("hits", Period::Minute, 120, 10, 12);
+
+        assert_eq!(0, r.remaining());
+        assert!(r.is_exhausted());
+        assert_eq!(Some(20), r.seconds_until_reset(&PeriodTime(100)));
+        assert_eq!(Some(0), r.seconds_until_reset(&PeriodTime(200)));
+
+        let eternity = report("hits", Period::Eternity, 120, 10, 1);
+        assert_eq!(None, eternity.seconds_until_reset(&PeriodTime(100)));
+
+        let disabled = report("hits", Period::Minute, 120, 0, 0);
+        assert!(disabled.is_disabled());
+        assert!(disabled.is_exhausted());
+        assert_eq!(None, disabled.seconds_until_reset(&PeriodTime(100)));
+    }
+
+    #[test]
+    fn finds_tightest_limits() {
+        let reports = UsageReports::UsageReports(vec![
+            report("hits", Period::Minute, 60, 10, 5),
+            report("hits", Period::Eternity, 0, 1000, 995),
+            report("hits", Period::Day, 86400, 100, 95),
+            report("other", Period::Day, 86400, 100, 1),
+        ]);
+
+        // both have 5 remaining, but the eternity limit never resets
+        assert_eq!(
+            Period::Eternity,
+            reports.tightest_for_metric("hits").unwrap().period
+        );
+        assert_eq!(Some(5), reports.remaining_for_metric("hits"));
+        assert_eq!(Some(99), reports.remaining_for_metric("other"));
+        assert_eq!(None, reports.remaining_for_metric("unknown"));
+        assert_eq!(3, reports.for_metric("hits").count());
+        assert_eq!(0, reports.exhausted().count());
+    }
+
+    #[test]
+    fn explains_denials() {
+        let now = PeriodTime(30);
+        let denied = status(
+            false,
+            vec![
+                report("hits", Period::Minute, 60, 10, 10),
+                report("hits", Period::Hour, 3600, 100, 100),
+                report("other", Period::Hour, 3600, 100, 1),
+            ],
+        );
+
+        let cause = denied.denial_cause().unwrap();
+        assert_eq!(
+            ("hits", Period::Hour),
+            (cause.metric.as_str(), cause.period)
+        );
+        assert_eq!(Some(3570), denied.retry_after(&now));
+        assert_eq!(Some(cause), denied.tightest_limit());
+
+        let disabled = status(false, vec![report("hits", Period::Minute, 60, 0, 0)]);
+        assert!(disabled.denial_cause().unwrap().is_disabled());
+        assert_eq!(None, disabled.retry_after(&now));
+
+        let authorized = status(true, vec![report("hits", Period::Minute, 60, 10, 1)]);
+        assert_eq!(None, authorized.denial_cause());
+        assert_eq!(None, authorized.retry_after(&now));
+    }
+
+    #[test]
+    fn ignores_limits_in_denials_for_other_reasons() {
+        let now = PeriodTime(30);
+        let mut denied = status(
+            false,
+            vec![
+                report("hits", Period::Minute, 60, 10, 10),
+                report("other", Period::Hour, 3600, 0, 0),
+            ],
+        );
+        denied.reason = Some("application key is invalid".into());
+
+        assert!(!denied.is_limits_exceeded());
+        assert_eq!(None, denied.denial_cause());
+        assert_eq!(None, denied.retry_after(&now));
+    }
+
+    #[test]
+    fn explains_denials_by_predicted_usage() {
+        let now = PeriodTime(30);
+        let denied = status(
+            false,
+            vec![
+                report("hits", Period::Minute, 60, 10, 8),
+                report("hits", Period::Hour, 3600, 100, 50),
+            ],
+        );
+
+        let cause = denied.denial_cause().unwrap();
+        assert_eq!(
+            ("hits", Period::Minute),
+            (cause.metric.as_str(), cause.period)
+        );
+        assert_eq!(Some(30), denied.retry_after(&now));
+    }
+
+    #[test]
+    fn explains_denials_without_reason_by_exceeded_limits() {
+        let mut denied = status(false, vec![report("hits", Period::Minute, 60, 10, 11)]);
+        denied.reason = None;
+
+        assert!(denied.is_limits_exceeded());
+        assert_eq!(Period::Minute, denied.denial_cause().unwrap().period);
+
+        let mut denied = status(false, vec![report("hits", Period::Minute, 60, 0, 0)]);
+        denied.reason = None;
+
+        assert!(!denied.is_limits_exceeded());
+        assert_eq!(None, denied.denial_cause());
+    }
+}
@@ -1,7 +1,9 @@
 use std::prelude::v1::*;
 
+#[cfg(feature = "xml-response")]
 use std::fmt;
 
+#[cfg(feature = "xml-response")]
 use serde::{
     de::{Deserializer, MapAccess, Visitor},
     Deserialize,
@@ -45,8 +47,10 @@ impl AppKeysList {
     }
 }
 
+#[cfg(feature = "xml-response")]
 struct AppKeysListVisitor;
 
+#[cfg(feature = "xml-response")]
 impl<'de> Visitor<'de> for AppKeysListVisitor {
     type Value = AppKeysList;
 
@@ -91,6 +95,7 @@ impl<'de> Visitor<'de> for AppKeysListVisitor {
     }
 }
 
+#[cfg(feature = "xml-response")]
 impl<'de> Deserialize<'de> for AppKeysList {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -0,0 +1,153 @@
+use std::prelude::v1::*;
+
+use std::fmt;
+
+#[cfg(feature = "xml-response")]
+use serde::Deserialize;
+
+/// The kind of problem an `ErrorCode` refers to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The credentials identifying the application or user are missing or invalid.
+    Credentials,
+    /// The application or user is not found or cannot be used.
+    Application,
+    /// The service or its credentials are invalid or misconfigured.
+    Service,
+    /// The usage or transaction data sent is invalid.
+    Usage,
+    /// The usage limits have been exceeded.
+    Limits,
+    /// The request is malformed or lacks required parameters.
+    Request,
+}
+
+macro_rules! error_codes {
+    { $($(#[$attr:meta])* $variant:ident => ($code:expr, $category:ident),)* } => {
+        /// Error codes returned by Apisonator.
+        ///
+        /// Codes not known to this crate are kept as `Unknown`.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "xml-response", derive(Deserialize))]
+        #[cfg_attr(feature = "xml-response", serde(from = "String"))]
+        #[non_exhaustive]
+        pub enum ErrorCode {
+            $($(#[$attr])* $variant,)*
+            Unknown(String),
+        }
+
+        impl ErrorCode {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(ErrorCode::$variant => $code,)*
+                    ErrorCode::Unknown(code) => code.as_str(),
+                }
+            }
+
+            /// The category of the error, or `None` for unknown codes.
+            pub fn category(&self) -> Option<ErrorCategory> {
+                match self {
+                    $(ErrorCode::$variant => Some(ErrorCategory::$category),)*
+                    ErrorCode::Unknown(_) => None,
+                }
+            }
+        }
+
+        impl From<&str> for ErrorCode {
+            fn from(code: &str) -> Self {
+                match code {
+                    $($code => ErrorCode::$variant,)*
+                    _ => ErrorCode::Unknown(code.to_owned()),
+                }
+            }
+        }
+
+        impl From<String> for ErrorCode {
+            fn from(code: String) -> Self {
+                match code.as_str() {
+                    $($code => ErrorCode::$variant,)*
+                    _ => ErrorCode::Unknown(code),
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    UserKeyInvalid => ("user_key_invalid", Credentials),
+    ApplicationKeyInvalid => ("application_key_invalid", Credentials),
+    AccessTokenInvalid => ("access_token_invalid", Credentials),
+    AccessTokenFormatInvalid => ("access_token_format_invalid", Credentials),
+    AccessTokenAlreadyExists => ("access_token_already_exists", Credentials),
+    ReferrerNotAllowed => ("referrer_not_allowed", Credentials),
+    RedirectUriInvalid => ("redirect_uri_invalid", Credentials),
+    RedirectUrlInvalid => ("redirect_url_invalid", Credentials),
+    ApplicationNotFound => ("application_not_found", Application),
+    ApplicationNotActive => ("application_not_active", Application),
+    ApplicationHasInconsistentData => ("application_has_inconsistent_data", Application),
+    ReferrerFiltersMissing => ("referrer_filters_missing", Application),
+    ReferrerFilterInvalid => ("referrer_filter_invalid", Application),
+    UserNotDefined => ("user_not_defined", Application),
+    UserRequiresRegistration => ("user_requires_registration", Application),
+    ProviderKeyInvalid => ("provider_key_invalid", Service),
+    ProviderKeyOrServiceTokenRequired => ("provider_key_or_service_token_required", Service),
+    ProviderKeyInvalidOrServiceMissing => ("provider_key_invalid_or_service_missing", Service),
+    ServiceIdInvalid => ("service_id_invalid", Service),
+    ServiceIdMissing => ("service_id_missing", Service),
+    ServiceTokenInvalid => ("service_token_invalid", Service),
+    OAuthNotEnabled => ("oauth_not_enabled", Service),
+    InvalidProviderKeys => ("invalid_provider_keys", Service),
+    ServiceIsDefaultService => ("service_is_default_service", Service),
+    ServiceRequiresDefaultUserPlan => ("service_requires_default_user_plan", Service),
+    EndUsersNoLongerSupported => ("end_users_no_longer_supported", Service),
+    AccessTokenStorageError => ("access_token_storage_error", Service),
+    UsageValueInvalid => ("usage_value_invalid", Usage),
+    MetricInvalid => ("metric_invalid", Usage),
+    TransactionsIsBlank => ("transactions_is_blank", Usage),
+    TransactionsFormatInvalid => ("transactions_format_invalid", Usage),
+    TransactionsHasNilTransaction => ("transactions_has_nil_transaction", Usage),
+    TransactionTimestampNotWithinRange => ("transaction_timestamp_not_within_range", Usage),
+    LimitsExceeded => ("limits_exceeded", Limits),
+    BadRequest => ("bad_request", Request),
+    ContentTypeInvalid => ("content_type_invalid", Request),
+    NotValidData => ("not_valid_data", Request),
+    RequiredParamsMissing => ("required_params_missing", Request),
+    AccessTokenInvalidTtl => ("access_token_invalid_ttl", Request),
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_known_codes() {
+        let code = ErrorCode::from("usage_value_invalid");
+
+        assert_eq!(ErrorCode::UsageValueInvalid, code);
+        assert_eq!("usage_value_invalid", code.as_str());
+        assert_eq!(Some(ErrorCategory::Usage), code.category());
+        assert_eq!(
+            ErrorCode::ProviderKeyInvalid,
+            ErrorCode::from(String::from("provider_key_invalid"))
+        );
+        assert_eq!(
+            Some(ErrorCategory::Request),
+            ErrorCode::from("required_params_missing").category()
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_codes() {
+        let code = ErrorCode::from("some_new_error");
+
+        assert_eq!(ErrorCode::Unknown("some_new_error".into()), code);
+        assert_eq!("some_new_error", code.to_string());
+        assert_eq!(None, code.category());
+    }
+}
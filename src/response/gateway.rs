@@ -0,0 +1,567 @@
+//! Mapping of authorization results to the HTTP responses a gateway sends to API consumers.
+//!
+//! A `GatewayPolicy` classifies an `Authorization` into an `Outcome` and decides whether the
+//! request should be passed through to the upstream API or rejected with a configurable status
+//! code, headers and body.
+//!
+//! # Examples
+//!
+//! ```
+//! use threescalers::response::{gateway::*, *};
+//!
+//! let body = r#"<status>
+//!     <authorized>false</authorized>
+//!     <reason>usage limits are exceeded</reason>
+//!     <plan>Basic</plan>
+//!     <usage_reports>
+//!         <usage_report metric="hits" period="minute">
+//!             <period_start>2019-06-05 16:24:00 +0000</period_start>
+//!             <period_end>2019-06-05 16:25:00 +0000</period_end>
+//!             <max_value>5</max_value>
+//!             <current_value>5</current_value>
+//!         </usage_report>
+//!     </usage_reports>
+//! </status>"#;
+//! let authorization = body.parse::<Authorization>().unwrap();
+//! let now = "2019-06-05 16:24:20 +0000".parse::<PeriodTime>().unwrap();
+//!
+//! match GatewayPolicy::new().map(&authorization, None, &now) {
+//!     GatewayAction::Respond(response) => {
+//!         assert_eq!(response.status(), 429);
+//!         assert_eq!(response.headers().get("Retry-After"), Some("40"));
+//!         assert_eq!(response.body(), "Limits exceeded");
+//!     }
+//!     GatewayAction::PassThrough(_) => unreachable!(),
+//! }
+//! ```
+use std::prelude::v1::*;
+
+use std::borrow::Cow;
+
+use crate::http::HeaderMap;
+
+use super::{
+    Authorization, AuthorizationResponse, AuthorizationStatus, ErrorCategory, ErrorCode,
+    PeriodTime, ResponseHeaders,
+};
+
+/// The classification of an authorization result from the point of view of a gateway.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The request can be passed through.
+    Authorized,
+    /// The request was denied because usage limits were reached.
+    LimitsExceeded,
+    /// The request did not carry the credentials required to identify the application or user.
+    CredentialsMissing,
+    /// The credentials are invalid, or the application or user can't be used.
+    CredentialsInvalid,
+    /// Apisonator could not be reached, or it rejected the call because of the service
+    /// configuration or the data sent rather than because of the API consumer.
+    BackendError,
+}
+
+impl Outcome {
+    /// Classifies an authorization, taking into account the rejection reason header if present.
+    pub fn classify(authorization: &Authorization, headers: Option<&ResponseHeaders>) -> Self {
+        if let Some(outcome) = headers
+            .and_then(ResponseHeaders::rejection_reason)
+            .and_then(|code| Self::from_code(code, authorization_reason(authorization)))
+        {
+            return outcome;
+        }
+
+        match authorization {
+            Authorization::Status(status) => Self::from_status(status),
+            Authorization::Error(error) => Self::from_code(error.error_code(), error.message())
+                .unwrap_or(Outcome::BackendError),
+        }
+    }
+
+    /// Classifies a response, which might not have a body when using the `no_body` extension.
+    pub fn classify_response(response: &AuthorizationResponse) -> Self {
+        if let Some(authorization) = response.authorization() {
+            return Self::classify(authorization, Some(response.headers()));
+        }
+
+        match response.status() {
+            200 => Outcome::Authorized,
+            status => response
+                .rejection_reason()
+                .and_then(|code| Self::from_code(code, None))
+                .unwrap_or(if status == 409 {
+                    // Apisonator denies calls with a 409 status code
+                    Outcome::CredentialsInvalid
+                } else {
+                    Outcome::BackendError
+                }),
+        }
+    }
+
+    fn from_status(status: &AuthorizationStatus) -> Self {
+        if status.authorized() {
+            Outcome::Authorized
+        } else if status.is_limits_exceeded() {
+            Outcome::LimitsExceeded
+        } else if is_missing(status.reason()) {
+            Outcome::CredentialsMissing
+        } else {
+            Outcome::CredentialsInvalid
+        }
+    }
+
+    fn from_code(code: &ErrorCode, reason: Option<&str>) -> Option<Self> {
+        Some(match code.category()? {
+            ErrorCategory::Limits => Outcome::LimitsExceeded,
+            ErrorCategory::Credentials | ErrorCategory::Application if is_missing(reason) => {
+                Outcome::CredentialsMissing
+            }
+            ErrorCategory::Credentials | ErrorCategory::Application => Outcome::CredentialsInvalid,
+            ErrorCategory::Service | ErrorCategory::Usage | ErrorCategory::Request => {
+                Outcome::BackendError
+            }
+        })
+    }
+}
+
+// Apisonator uses the same codes for missing and invalid credentials, so look at the reason.
+fn is_missing(reason: Option<&str>) -> bool {
+    match reason {
+        Some(reason) => reason.ends_with("is missing"),
+        None => false,
+    }
+}
+
+fn authorization_reason(authorization: &Authorization) -> Option<&str> {
+    match authorization {
+        Authorization::Status(status) => status.reason(),
+        Authorization::Error(error) => error.message(),
+    }
+}
+
+/// The response sent to API consumers for a given outcome.
+///
+/// The body is a template in which `{code}` and `{reason}` are replaced with the error code and
+/// the human-readable reason sent by Apisonator, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    status: u16,
+    content_type: Cow<'static, str>,
+    body: Cow<'static, str>,
+}
+
+impl Rejection {
+    pub fn new<B: Into<Cow<'static, str>>>(status: u16, body: B) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8".into(),
+            body: body.into(),
+        }
+    }
+
+    pub fn with_content_type<C: Into<Cow<'static, str>>>(mut self, content_type: C) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn content_type(&self) -> &str {
+        self.content_type.as_ref()
+    }
+
+    pub fn body(&self) -> &str {
+        self.body.as_ref()
+    }
+
+    fn render(&self, code: Option<&ErrorCode>, reason: Option<&str>) -> String {
+        self.body
+            .replace("{code}", code.map_or("", ErrorCode::as_str))
+            .replace("{reason}", reason.unwrap_or(""))
+    }
+}
+
+/// What a gateway should do with a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GatewayAction {
+    /// Pass the request through to the upstream API, adding these headers to its response.
+    PassThrough(HeaderMap),
+    /// Reject the request with this response.
+    Respond(GatewayResponse),
+}
+
+impl GatewayAction {
+    pub fn is_pass_through(&self) -> bool {
+        match self {
+            GatewayAction::PassThrough(_) => true,
+            GatewayAction::Respond(_) => false,
+        }
+    }
+}
+
+/// A response to be sent to an API consumer instead of passing the request through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayResponse {
+    outcome: Outcome,
+    status: u16,
+    headers: HeaderMap,
+    body: String,
+}
+
+impl GatewayResponse {
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn body(&self) -> &str {
+        self.body.as_str()
+    }
+
+    pub fn into_parts(self) -> (u16, HeaderMap, String) {
+        (self.status, self.headers, self.body)
+    }
+}
+
+/// Decides how a gateway responds to each `Outcome`.
+///
+/// By default rejections use the same status codes and bodies as APIcast, backend errors fail
+/// closed with a 503 status code, and `RateLimit-*` headers are added when limit headers are
+/// available.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayPolicy {
+    limits_exceeded: Rejection,
+    credentials_missing: Rejection,
+    credentials_invalid: Rejection,
+    backend_error: Rejection,
+    fail_open: bool,
+    rate_limit_headers: bool,
+}
+
+impl Default for GatewayPolicy {
+    fn default() -> Self {
+        Self {
+            limits_exceeded: Rejection::new(429, "Limits exceeded"),
+            credentials_missing: Rejection::new(401, "Authentication parameters missing"),
+            credentials_invalid: Rejection::new(403, "Authentication failed"),
+            backend_error: Rejection::new(503, "Service Unavailable"),
+            fail_open: false,
+            rate_limit_headers: true,
+        }
+    }
+}
+
+impl GatewayPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits_exceeded(mut self, rejection: Rejection) -> Self {
+        self.limits_exceeded = rejection;
+        self
+    }
+
+    pub fn with_credentials_missing(mut self, rejection: Rejection) -> Self {
+        self.credentials_missing = rejection;
+        self
+    }
+
+    pub fn with_credentials_invalid(mut self, rejection: Rejection) -> Self {
+        self.credentials_invalid = rejection;
+        self
+    }
+
+    pub fn with_backend_error(mut self, rejection: Rejection) -> Self {
+        self.backend_error = rejection;
+        self
+    }
+
+    /// Whether requests should be passed through when Apisonator fails or can't be reached.
+    pub fn with_fail_open(mut self, fail_open: bool) -> Self {
+        self.fail_open = fail_open;
+        self
+    }
+
+    /// Whether `RateLimit-*` headers should be built out of the limit headers.
+    pub fn with_rate_limit_headers(mut self, rate_limit_headers: bool) -> Self {
+        self.rate_limit_headers = rate_limit_headers;
+        self
+    }
+
+    pub fn rejection(&self, outcome: Outcome) -> Option<&Rejection> {
+        match outcome {
+            Outcome::Authorized => None,
+            Outcome::LimitsExceeded => Some(&self.limits_exceeded),
+            Outcome::CredentialsMissing => Some(&self.credentials_missing),
+            Outcome::CredentialsInvalid => Some(&self.credentials_invalid),
+            Outcome::BackendError => Some(&self.backend_error),
+        }
+    }
+
+    /// Maps an authorization, along with the limit headers sent with it if any. `now` is used to
+    /// compute the `Retry-After` header out of the end of the period of the exceeded limit.
+    pub fn map(
+        &self,
+        authorization: &Authorization,
+        headers: Option<&ResponseHeaders>,
+        now: &PeriodTime,
+    ) -> GatewayAction {
+        let outcome = Outcome::classify(authorization, headers);
+        let (code, reason, retry_after) = match authorization {
+            Authorization::Status(status) => (None, status.reason(), status.retry_after(now)),
+            Authorization::Error(error) => (Some(error.error_code()), error.message(), None),
+        };
+        let code = headers.and_then(ResponseHeaders::rejection_reason).or(code);
+
+        self.action(outcome, headers, code, reason, retry_after)
+    }
+
+    /// Maps a response, which might not have a body when using the `no_body` extension.
+    pub fn map_response(
+        &self,
+        response: &AuthorizationResponse,
+        now: &PeriodTime,
+    ) -> GatewayAction {
+        match response.authorization() {
+            Some(authorization) => self.map(authorization, Some(response.headers()), now),
+            None => self.action(
+                Outcome::classify_response(response),
+                Some(response.headers()),
+                response.rejection_reason(),
+                None,
+                None,
+            ),
+        }
+    }
+
+    /// Maps a failure to reach Apisonator or to parse its response.
+    pub fn map_backend_failure(&self) -> GatewayAction {
+        self.action(Outcome::BackendError, None, None, None, None)
+    }
+
+    fn action(
+        &self,
+        outcome: Outcome,
+        limit_headers: Option<&ResponseHeaders>,
+        code: Option<&ErrorCode>,
+        reason: Option<&str>,
+        retry_after: Option<u64>,
+    ) -> GatewayAction {
+        let mut headers = match limit_headers {
+            Some(limit_headers) if self.rate_limit_headers => limit_headers.rate_limit_headers(),
+            _ => HeaderMap::new(),
+        };
+
+        let rejection = match self.rejection(outcome) {
+            Some(_) if outcome == Outcome::BackendError && self.fail_open => {
+                return GatewayAction::PassThrough(headers)
+            }
+            Some(rejection) => rejection,
+            None => return GatewayAction::PassThrough(headers),
+        };
+
+        if outcome == Outcome::LimitsExceeded {
+            let retry_after = retry_after.or_else(|| {
+                limit_headers
+                    .and_then(ResponseHeaders::limit_reset)
+                    .filter(|&reset| reset >= 0)
+                    .map(|reset| reset as u64)
+            });
+            if let Some(retry_after) = retry_after {
+                headers.insert("Retry-After".into(), retry_after.to_string());
+            }
+        }
+        headers.insert("Content-Type".into(), rejection.content_type().into());
+
+        GatewayAction::Respond(GatewayResponse {
+            outcome,
+            status: rejection.status(),
+            headers,
+            body: rejection.render(code, reason),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::analytics::LIMITS_EXCEEDED_REASON;
+
+    fn parse_authorization(body: &str) -> Authorization {
+        body.parse().unwrap()
+    }
+
+    const NOW: PeriodTime = PeriodTime(1_559_751_860);
+
+    fn status(authorized: &str, reason: &str, current_value: u64) -> Authorization {
+        parse_authorization(&format!(
+            r#"<status>
+                <authorized>{}</authorized>
+                <reason>{}</reason>
+                <plan>Basic</plan>
+                <usage_reports>
+                    <usage_report metric="hits" period="minute">
+                        <period_start>2019-06-05 16:24:00 +0000</period_start>
+                        <period_end>2019-06-05 16:25:00 +0000</period_end>
+                        <max_value>5</max_value>
+                        <current_value>{}</current_value>
+                    </usage_report>
+                </usage_reports>
+            </status>"#,
+            authorized, reason, current_value
+        ))
+    }
+
+    fn respond(action: GatewayAction) -> GatewayResponse {
+        match action {
+            GatewayAction::Respond(response) => response,
+            GatewayAction::PassThrough(_) => unreachable!(),
+        }
+    }
+
+    fn limit_headers() -> ResponseHeaders {
+        let headers = vec![
+            ("3scale-limit-remaining", "3"),
+            ("3scale-limit-reset", "40"),
+            ("3scale-limit-max-value", "5"),
+        ]
+        .into_iter()
+        .collect::<HeaderMap>();
+
+        ResponseHeaders::from_header_map(&headers).unwrap()
+    }
+
+    #[test]
+    fn passes_authorized_requests_through() {
+        let authorization = status("true", "", 2);
+        let headers = limit_headers();
+
+        match GatewayPolicy::new().map(&authorization, Some(&headers), &NOW) {
+            GatewayAction::PassThrough(headers) => {
+                assert_eq!(Some("3"), headers.get("RateLimit-Remaining"));
+            }
+            GatewayAction::Respond(_) => unreachable!(),
+        }
+
+        let policy = GatewayPolicy::new().with_rate_limit_headers(false);
+        assert_eq!(
+            GatewayAction::PassThrough(HeaderMap::new()),
+            policy.map(&authorization, Some(&headers), &NOW)
+        );
+    }
+
+    #[test]
+    fn rejects_exceeded_limits() {
+        let authorization = status("false", LIMITS_EXCEEDED_REASON, 5);
+
+        let response = respond(GatewayPolicy::new().map(&authorization, None, &NOW));
+
+        assert_eq!(Outcome::LimitsExceeded, response.outcome());
+        assert_eq!(429, response.status());
+        assert_eq!(Some("40"), response.headers().get("Retry-After"));
+        assert_eq!(
+            Some("text/plain; charset=utf-8"),
+            response.headers().get("Content-Type")
+        );
+    }
+
+    #[test]
+    fn tells_missing_from_invalid_credentials() {
+        let missing = status("false", "application key is missing", 0);
+        let invalid = parse_authorization(
+            r#"<error code="user_key_invalid">user key "a" is invalid</error>"#,
+        );
+        let policy = GatewayPolicy::new().with_credentials_invalid(
+            Rejection::new(403, r#"{"error":"{code}"}"#).with_content_type("application/json"),
+        );
+
+        let response = respond(policy.map(&missing, None, &NOW));
+        assert_eq!(Outcome::CredentialsMissing, response.outcome());
+        assert_eq!(401, response.status());
+
+        let response = respond(policy.map(&invalid, None, &NOW));
+        assert_eq!(Outcome::CredentialsInvalid, response.outcome());
+        assert_eq!(403, response.status());
+        assert_eq!(r#"{"error":"user_key_invalid"}"#, response.body());
+        assert_eq!(
+            Some("application/json"),
+            response.headers().get("Content-Type")
+        );
+    }
+
+    #[test]
+    fn tells_invalid_credentials_from_disabled_metrics() {
+        let authorization = parse_authorization(
+            r#"<status>
+                <authorized>false</authorized>
+                <reason>application key "x" is invalid</reason>
+                <plan>Basic</plan>
+                <usage_reports>
+                    <usage_report metric="hits" period="minute">
+                        <period_start>2019-06-05 16:24:00 +0000</period_start>
+                        <period_end>2019-06-05 16:25:00 +0000</period_end>
+                        <max_value>0</max_value>
+                        <current_value>0</current_value>
+                    </usage_report>
+                </usage_reports>
+            </status>"#,
+        );
+
+        let response = respond(GatewayPolicy::new().map(&authorization, None, &NOW));
+        assert_eq!(Outcome::CredentialsInvalid, response.outcome());
+        assert_eq!(403, response.status());
+    }
+
+    #[test]
+    fn fails_open_or_closed_on_backend_errors() {
+        let error = parse_authorization(
+            r#"<error code="provider_key_invalid">provider key "a" is invalid</error>"#,
+        );
+
+        let response = respond(GatewayPolicy::new().map(&error, None, &NOW));
+        assert_eq!(Outcome::BackendError, response.outcome());
+        assert_eq!(503, response.status());
+        assert_eq!(
+            503,
+            respond(GatewayPolicy::new().map_backend_failure()).status()
+        );
+
+        let policy = GatewayPolicy::new().with_fail_open(true);
+        assert!(policy.map(&error, None, &NOW).is_pass_through());
+        assert!(policy.map_backend_failure().is_pass_through());
+    }
+
+    #[test]
+    fn maps_responses_without_body() {
+        let headers = vec![
+            ("3scale-rejection-reason", "limits_exceeded"),
+            ("3scale-limit-reset", "12"),
+        ]
+        .into_iter()
+        .collect::<HeaderMap>();
+        let policy = GatewayPolicy::new();
+
+        let denied = AuthorizationResponse::from_parts(409, &headers, None).unwrap();
+        let response = respond(policy.map_response(&denied, &NOW));
+        assert_eq!(429, response.status());
+        assert_eq!(Some("12"), response.headers().get("Retry-After"));
+
+        let authorized = AuthorizationResponse::from_parts(200, &HeaderMap::new(), None).unwrap();
+        assert!(policy.map_response(&authorized, &NOW).is_pass_through());
+
+        let failed = AuthorizationResponse::from_parts(500, &HeaderMap::new(), None).unwrap();
+        assert_eq!(
+            Outcome::BackendError,
+            respond(policy.map_response(&failed, &NOW)).outcome()
+        );
+    }
+}
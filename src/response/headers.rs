@@ -0,0 +1,175 @@
+use std::prelude::v1::*;
+
+use crate::{anyhow, http::HeaderMap, Error};
+
+use super::ErrorCode;
+
+pub const LIMIT_REMAINING_HEADER: &str = "3scale-limit-remaining";
+pub const LIMIT_RESET_HEADER: &str = "3scale-limit-reset";
+pub const LIMIT_MAX_VALUE_HEADER: &str = "3scale-limit-max-value";
+pub const REJECTION_REASON_HEADER: &str = "3scale-rejection-reason";
+
+/// Information sent by Apisonator in response headers when asked to via the `LimitHeaders` and
+/// `RejectionReasonHeader` extensions.
+///
+/// Limit values refer to the most constrained usage limit. Apisonator uses -1 to signal that no
+/// limit applies.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::{http::HeaderMap, response::*};
+///
+/// let headers = vec![
+///     ("3scale-limit-remaining", "0"),
+///     ("3scale-limit-reset", "42"),
+///     ("3scale-rejection-reason", "limits_exceeded"),
+/// ]
+/// .into_iter()
+/// .collect::<HeaderMap>();
+///
+/// let response_headers = ResponseHeaders::from_header_map(&headers).unwrap();
+///
+/// assert_eq!(response_headers.limit_remaining(), Some(0));
+/// assert_eq!(response_headers.limit_reset(), Some(42));
+/// assert_eq!(response_headers.limit_max_value(), None);
+/// assert_eq!(response_headers.rejection_reason(), Some(&ErrorCode::LimitsExceeded));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseHeaders {
+    limit_remaining: Option<i64>,
+    limit_reset: Option<i64>,
+    limit_max_value: Option<i64>,
+    rejection_reason: Option<ErrorCode>,
+}
+
+impl ResponseHeaders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the limit and rejection reason headers found in a `HeaderMap`, ignoring others.
+    pub fn from_header_map(headers: &HeaderMap) -> Result<Self, Error> {
+        let limit = |name| {
+            headers
+                .get(name)
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<i64>()
+                        .map_err(|e| anyhow!("invalid {} header {:?}: {}", name, value, e))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            limit_remaining: limit(LIMIT_REMAINING_HEADER)?,
+            limit_reset: limit(LIMIT_RESET_HEADER)?,
+            limit_max_value: limit(LIMIT_MAX_VALUE_HEADER)?,
+            rejection_reason: headers
+                .get(REJECTION_REASON_HEADER)
+                .map(|value| ErrorCode::from(value.trim())),
+        })
+    }
+
+    /// The remaining usage allowed by the most constrained limit.
+    pub fn limit_remaining(&self) -> Option<i64> {
+        self.limit_remaining
+    }
+
+    /// The number of seconds until the most constrained limit is reset.
+    pub fn limit_reset(&self) -> Option<i64> {
+        self.limit_reset
+    }
+
+    /// The maximum value of the most constrained limit.
+    pub fn limit_max_value(&self) -> Option<i64> {
+        self.limit_max_value
+    }
+
+    pub fn rejection_reason(&self) -> Option<&ErrorCode> {
+        self.rejection_reason.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limit_remaining.is_none()
+            && self.limit_reset.is_none()
+            && self.limit_max_value.is_none()
+            && self.rejection_reason.is_none()
+    }
+
+    /// Builds the standard `RateLimit-*` headers to forward to API consumers, leaving out
+    /// values for which no limit applies.
+    pub fn rate_limit_headers(&self) -> HeaderMap {
+        [
+            ("RateLimit-Limit", self.limit_max_value),
+            ("RateLimit-Remaining", self.limit_remaining),
+            ("RateLimit-Reset", self.limit_reset),
+        ]
+        .iter()
+        .filter_map(|&(name, value)| {
+            value
+                .filter(|&v| v >= 0)
+                .map(|v| (name.to_owned(), v.to_string()))
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_headers_case_insensitively() {
+        let headers = vec![
+            ("3Scale-Limit-Remaining", "5"),
+            ("3SCALE-LIMIT-RESET", " 60 "),
+            ("3scale-limit-max-value", "10"),
+            ("3scale-rejection-reason", "some_new_reason"),
+            ("Content-Type", "application/xml"),
+        ]
+        .into_iter()
+        .collect::<HeaderMap>();
+
+        let parsed = ResponseHeaders::from_header_map(&headers).unwrap();
+
+        assert_eq!(Some(5), parsed.limit_remaining());
+        assert_eq!(Some(60), parsed.limit_reset());
+        assert_eq!(Some(10), parsed.limit_max_value());
+        assert_eq!(
+            Some(&ErrorCode::Unknown("some_new_reason".into())),
+            parsed.rejection_reason()
+        );
+    }
+
+    #[test]
+    fn fails_on_invalid_limits() {
+        let headers = vec![("3scale-limit-remaining", "many")]
+            .into_iter()
+            .collect::<HeaderMap>();
+
+        assert!(ResponseHeaders::from_header_map(&headers).is_err());
+    }
+
+    #[test]
+    fn skips_unlimited_values_in_rate_limit_headers() {
+        let headers = vec![
+            ("3scale-limit-remaining", "-1"),
+            ("3scale-limit-reset", "-1"),
+            ("3scale-limit-max-value", "100"),
+        ]
+        .into_iter()
+        .collect::<HeaderMap>();
+
+        let rate_limit_headers = ResponseHeaders::from_header_map(&headers)
+            .unwrap()
+            .rate_limit_headers();
+
+        assert_eq!(
+            vec![("RateLimit-Limit".to_owned(), "100".to_owned())],
+            rate_limit_headers.into_iter().collect::<Vec<_>>()
+        );
+        assert!(ResponseHeaders::new().is_empty());
+    }
+}
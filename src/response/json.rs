@@ -0,0 +1,555 @@
+//! A JSON friendly `serde` representation of response types, enabled by the `json-response`
+//! feature.
+//!
+//! The `Deserialize` implementations of response types follow the layout of the XML documents
+//! sent by Apisonator, which is awkward to use with other formats. The `Json` wrapper implements
+//! `Serialize` and `Deserialize` with a stable schema instead:
+//!
+//! - `Period` is its lowercase name, ie. `"minute"` or `"eternity"`.
+//! - `PeriodTime` is the number of seconds since the UNIX epoch.
+//! - `UsageReport` is an object with the `metric`, `period`, `period_start`, `period_end`,
+//!   `max_value` and `current_value` fields, and `UsageReports` is an array of them.
+//! - `MetricsHierarchy` is an object mapping parent metrics to arrays of children metrics.
+//! - `AppKeysList` is an object with the `service_id`, `app_id` and `keys` fields.
+//! - `Authorization` is either `{"status": {...}}` or `{"error": {"code": ..., "message": ...}}`.
+//!
+//! Each type also has a module with `serialize` and `deserialize` functions for use with
+//! `#[serde(with = "...")]` in fields of other types.
+//!
+//! # Examples
+//!
+//! ```
+//! use threescalers::response::{json::Json, Period, PeriodTime, UsageReport};
+//!
+//! let report = UsageReport {
+//!     metric: "hits".into(),
+//!     period: Period::Hour,
+//!     period_start: PeriodTime(1_559_750_400),
+//!     period_end: PeriodTime(1_559_754_000),
+//!     max_value: 100,
+//!     current_value: 5,
+//! };
+//!
+//! let json = serde_json::to_string(&Json(&report)).unwrap();
+//! assert_eq!(
+//!     json,
+//!     r#"{"metric":"hits","period":"hour","period_start":1559750400,"period_end":1559754000,"max_value":100,"current_value":5}"#
+//! );
+//!
+//! let Json(parsed) = serde_json::from_str::<Json<UsageReport>>(&json).unwrap();
+//! assert_eq!(parsed, report);
+//! ```
+use std::prelude::v1::*;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    AppKeysList, Authorization, AuthorizationError, AuthorizationStatus, MetricsHierarchy,
+    OAuthApplication, Period, PeriodTime, UsageReport, UsageReports,
+};
+
+/// Wraps a response type, or a reference to it, to serialize and deserialize it using the JSON
+/// friendly representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+macro_rules! json_impls {
+    { $($ty:ty => $module:ident,)* } => {
+        $(
+            impl Serialize for Json<$ty> {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    $module::serialize(&self.0, serializer)
+                }
+            }
+
+            impl Serialize for Json<&$ty> {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    $module::serialize(self.0, serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for Json<$ty> {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    $module::deserialize(deserializer).map(Json)
+                }
+            }
+        )*
+    };
+}
+
+json_impls! {
+    Authorization => authorization,
+    AuthorizationStatus => authorization_status,
+    AuthorizationError => authorization_error,
+    UsageReports => usage_reports,
+    UsageReport => usage_report,
+    Period => period,
+    PeriodTime => period_time,
+    MetricsHierarchy => metrics_hierarchy,
+    AppKeysList => app_keys_list,
+    OAuthApplication => oauth_application,
+}
+
+pub mod authorization {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Repr {
+        Status(Json<AuthorizationStatus>),
+        Error(Json<AuthorizationError>),
+    }
+
+    pub fn serialize<S: Serializer>(
+        authorization: &Authorization,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match authorization {
+            Authorization::Status(status) => {
+                serializer.serialize_newtype_variant("Authorization", 0, "status", &Json(status))
+            }
+            Authorization::Error(error) => {
+                serializer.serialize_newtype_variant("Authorization", 1, "error", &Json(error))
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Authorization, D::Error> {
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Status(Json(status)) => Authorization::Status(status),
+            Repr::Error(Json(error)) => Authorization::Error(error),
+        })
+    }
+}
+
+pub mod authorization_status {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct ReprRef<'a> {
+        authorized: bool,
+        reason: Option<&'a str>,
+        plan: &'a str,
+        usage_reports: Option<Json<&'a UsageReports>>,
+        hierarchy: Option<Json<&'a MetricsHierarchy>>,
+        app_keys: Option<Json<&'a AppKeysList>>,
+        application: Option<Json<&'a OAuthApplication>>,
+    }
+
+    #[derive(Deserialize)]
+    struct Repr {
+        authorized: bool,
+        #[serde(default)]
+        reason: Option<String>,
+        plan: String,
+        #[serde(default)]
+        usage_reports: Option<Json<UsageReports>>,
+        #[serde(default)]
+        hierarchy: Option<Json<MetricsHierarchy>>,
+        #[serde(default)]
+        app_keys: Option<Json<AppKeysList>>,
+        #[serde(default)]
+        application: Option<Json<OAuthApplication>>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        status: &AuthorizationStatus,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ReprRef {
+            authorized: status.authorized,
+            reason: status.reason.as_deref(),
+            plan: status.plan.as_str(),
+            usage_reports: status.usage_reports.as_ref().map(Json),
+            hierarchy: status.metrics_hierarchy.as_ref().map(Json),
+            app_keys: status.app_keys.as_ref().map(Json),
+            application: status.application.as_deref().map(Json),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<AuthorizationStatus, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+
+        Ok(AuthorizationStatus {
+            authorized: repr.authorized,
+            reason: repr.reason,
+            plan: repr.plan,
+            usage_reports: repr.usage_reports.map(Json::into_inner),
+            metrics_hierarchy: repr.hierarchy.map(Json::into_inner),
+            app_keys: repr.app_keys.map(Json::into_inner),
+            application: repr.application.map(|a| Box::new(a.into_inner())),
+        })
+    }
+}
+
+pub mod authorization_error {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct ReprRef<'a> {
+        code: &'a str,
+        message: Option<&'a str>,
+    }
+
+    #[derive(Deserialize)]
+    struct Repr {
+        code: String,
+        #[serde(default)]
+        message: Option<String>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        error: &AuthorizationError,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ReprRef {
+            code: error.code(),
+            message: error.message(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<AuthorizationError, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+
+        Ok(AuthorizationError {
+            code: repr.code.into(),
+            message: repr.message,
+        })
+    }
+}
+
+pub mod usage_reports {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        reports: &UsageReports,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(reports.iter().map(Json))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<UsageReports, D::Error> {
+        let reports = Vec::<Json<UsageReport>>::deserialize(deserializer)?;
+
+        Ok(UsageReports::UsageReports(
+            reports.into_iter().map(Json::into_inner).collect(),
+        ))
+    }
+}
+
+pub mod usage_report {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct ReprRef<'a> {
+        metric: &'a str,
+        period: Json<Period>,
+        period_start: Json<&'a PeriodTime>,
+        period_end: Json<&'a PeriodTime>,
+        max_value: u64,
+        current_value: u64,
+    }
+
+    #[derive(Deserialize)]
+    struct Repr {
+        metric: String,
+        period: Json<Period>,
+        period_start: Json<PeriodTime>,
+        period_end: Json<PeriodTime>,
+        max_value: u64,
+        current_value: u64,
+    }
+
+    pub fn serialize<S: Serializer>(
+        report: &UsageReport,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ReprRef {
+            metric: report.metric.as_str(),
+            period: Json(report.period),
+            period_start: Json(&report.period_start),
+            period_end: Json(&report.period_end),
+            max_value: report.max_value,
+            current_value: report.current_value,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<UsageReport, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+
+        Ok(UsageReport {
+            metric: repr.metric,
+            period: repr.period.0,
+            period_start: repr.period_start.0,
+            period_end: repr.period_end.0,
+            max_value: repr.max_value,
+            current_value: repr.current_value,
+        })
+    }
+}
+
+pub mod period {
+    use super::*;
+
+    use serde::de::Error;
+
+    const PERIODS: &[&str] = &["minute", "hour", "day", "week", "month", "year", "eternity"];
+
+    pub fn serialize<S: Serializer>(period: &Period, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(period.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Period, D::Error> {
+        let period = String::deserialize(deserializer)?;
+
+        period
+            .parse()
+            .map_err(|_| D::Error::unknown_variant(&period, PERIODS))
+    }
+}
+
+pub mod period_time {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(time: &PeriodTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(time.0)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PeriodTime, D::Error> {
+        i64::deserialize(deserializer).map(PeriodTime)
+    }
+}
+
+pub mod metrics_hierarchy {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(
+        hierarchy: &MetricsHierarchy,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(hierarchy.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MetricsHierarchy, D::Error> {
+        let map = BTreeMap::<String, Vec<String>>::deserialize(deserializer)?;
+        let mut hierarchy = MetricsHierarchy::new();
+
+        for (parent_metric, children_metrics) in map {
+            hierarchy.insert(parent_metric, children_metrics);
+        }
+
+        Ok(hierarchy)
+    }
+}
+
+pub mod app_keys_list {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct ReprRef<'a> {
+        service_id: Option<&'a str>,
+        app_id: Option<&'a str>,
+        keys: Vec<&'a str>,
+    }
+
+    #[derive(Deserialize)]
+    struct Repr {
+        #[serde(default)]
+        service_id: Option<String>,
+        #[serde(default)]
+        app_id: Option<String>,
+        #[serde(default)]
+        keys: Vec<String>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        app_keys: &AppKeysList,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ReprRef {
+            service_id: app_keys.service_id().map(AsRef::as_ref),
+            app_id: app_keys.app_id().map(AsRef::as_ref),
+            keys: app_keys.keys().iter().map(AsRef::as_ref).collect(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<AppKeysList, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+
+        Ok(AppKeysList::new(repr.service_id, repr.app_id, repr.keys))
+    }
+}
+
+pub mod oauth_application {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct ReprRef<'a> {
+        id: &'a str,
+        key: Option<&'a str>,
+        redirect_url: Option<&'a str>,
+    }
+
+    #[derive(Deserialize)]
+    struct Repr {
+        id: String,
+        #[serde(default)]
+        key: Option<String>,
+        #[serde(default)]
+        redirect_url: Option<String>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        application: &OAuthApplication,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ReprRef {
+            id: application.id.as_str(),
+            key: application.key.as_deref(),
+            redirect_url: application.redirect_url.as_deref(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OAuthApplication, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+
+        Ok(OAuthApplication::new(repr.id, repr.key, repr.redirect_url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::ErrorCode;
+
+    fn status() -> Authorization {
+        let mut hierarchy = MetricsHierarchy::new();
+        hierarchy.insert("hits", vec!["products".to_owned(), "sales".to_owned()]);
+
+        Authorization::Status(AuthorizationStatus {
+            authorized: false,
+            reason: Some("usage limits are exceeded".into()),
+            plan: "Gold".into(),
+            usage_reports: Some(UsageReports::UsageReports(vec![UsageReport {
+                metric: "hits".into(),
+                period: Period::Eternity,
+                period_start: PeriodTime(-1),
+                period_end: PeriodTime(9_223_372_036_854_775_807),
+                max_value: 18_446_744_073_709_551_615,
+                current_value: 6,
+            }])),
+            metrics_hierarchy: Some(hierarchy),
+            app_keys: Some(AppKeysList::new(None::<&str>, Some("app"), vec!["key_1"])),
+            application: Some(Box::new(OAuthApplication::new("app", Some(""), None))),
+        })
+    }
+
+    #[test]
+    fn keeps_a_stable_schema() {
+        let json = serde_json::to_value(Json(status())).unwrap();
+
+        assert_eq!(
+            serde_json::json!({
+                "status": {
+                    "authorized": false,
+                    "reason": "usage limits are exceeded",
+                    "plan": "Gold",
+                    "usage_reports": [{
+                        "metric": "hits",
+                        "period": "eternity",
+                        "period_start": -1,
+                        "period_end": 9_223_372_036_854_775_807_i64,
+                        "max_value": 18_446_744_073_709_551_615_u64,
+                        "current_value": 6
+                    }],
+                    "hierarchy": { "hits": ["products", "sales"] },
+                    "app_keys": { "service_id": null, "app_id": "app", "keys": ["key_1"] },
+                    "application": { "id": "app", "key": "", "redirect_url": null }
+                }
+            }),
+            json
+        );
+    }
+
+    #[test]
+    fn roundtrips_authorizations() {
+        let error = Authorization::Error(AuthorizationError {
+            code: ErrorCode::Unknown("some_new_error".into()),
+            message: None,
+        });
+
+        for authorization in &[status(), error] {
+            let json = serde_json::to_string(&Json(authorization)).unwrap();
+            let parsed = serde_json::from_str::<Json<Authorization>>(&json).unwrap();
+
+            assert_eq!(authorization, &parsed.into_inner());
+        }
+    }
+
+    #[test]
+    fn accepts_missing_optional_fields() {
+        let json = r#"{"status": {"authorized": true, "plan": "Basic"}}"#;
+
+        let Json(authorization) = serde_json::from_str::<Json<Authorization>>(json).unwrap();
+
+        match authorization {
+            Authorization::Status(status) => {
+                assert!(status.authorized());
+                assert!(status.usage_reports().is_none());
+            }
+            _ => unreachable!(),
+        }
+        assert!(serde_json::from_str::<Json<Period>>(r#""fortnight""#).is_err());
+    }
+
+    #[test]
+    fn works_with_serde_attributes() {
+        #[derive(Serialize, Deserialize)]
+        struct CacheEntry {
+            #[serde(with = "authorization")]
+            authorization: Authorization,
+            #[serde(with = "period_time")]
+            expires_at: PeriodTime,
+        }
+
+        let entry = CacheEntry {
+            authorization: status(),
+            expires_at: PeriodTime(60),
+        };
+
+        let json = serde_json::to_string(&entry).unwrap();
+        let parsed = serde_json::from_str::<CacheEntry>(&json).unwrap();
+
+        assert_eq!(entry.authorization, parsed.authorization);
+        assert_eq!(entry.expires_at, parsed.expires_at);
+    }
+}
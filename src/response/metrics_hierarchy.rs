@@ -1,13 +1,14 @@
 use std::prelude::v1::*;
 
-use std::{
-    collections::{
-        btree_map::{Iter, IterMut},
-        BTreeMap,
-    },
-    fmt,
+use std::collections::{
+    btree_map::{Iter, IterMut},
+    BTreeMap,
 };
 
+#[cfg(feature = "xml-response")]
+use std::fmt;
+
+#[cfg(feature = "xml-response")]
 use serde::{
     de::{self, Deserializer, MapAccess, Visitor},
     Deserialize,
@@ -60,8 +61,10 @@ impl MetricsHierarchy {
     }
 }
 
+#[cfg(feature = "xml-response")]
 struct MetricsHierarchyVisitor;
 
+#[cfg(feature = "xml-response")]
 impl<'de> Visitor<'de> for MetricsHierarchyVisitor {
     type Value = MetricsHierarchy;
 
@@ -97,6 +100,7 @@ impl<'de> Visitor<'de> for MetricsHierarchyVisitor {
     }
 }
 
+#[cfg(feature = "xml-response")]
 impl<'de> Deserialize<'de> for MetricsHierarchy {
     fn deserialize<D>(deserializer: D) -> Result<MetricsHierarchy, D::Error>
     where
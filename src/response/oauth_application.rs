@@ -0,0 +1,40 @@
+use std::prelude::v1::*;
+
+#[cfg(feature = "xml-response")]
+use serde::Deserialize;
+
+/// The application data returned by the OAuth endpoints, used by OAuth servers to validate
+/// clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
+pub struct OAuthApplication {
+    pub(super) id: String,
+    #[cfg_attr(feature = "xml-response", serde(default))]
+    pub(super) key: Option<String>,
+    #[cfg_attr(feature = "xml-response", serde(default))]
+    pub(super) redirect_url: Option<String>,
+}
+
+impl OAuthApplication {
+    pub fn new<S: Into<String>>(id: S, key: Option<S>, redirect_url: Option<S>) -> Self {
+        Self {
+            id: id.into(),
+            key: key.map(Into::into),
+            redirect_url: redirect_url.map(Into::into),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// The application key, if the application has any.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref().filter(|k| !k.is_empty())
+    }
+
+    /// The redirection URL configured for the application, if any.
+    pub fn redirect_url(&self) -> Option<&str> {
+        self.redirect_url.as_deref().filter(|u| !u.is_empty())
+    }
+}
@@ -0,0 +1,209 @@
+use std::prelude::v1::*;
+
+#[cfg(feature = "xml-response")]
+use serde::Deserialize;
+
+use crate::{anyhow, http::HeaderMap, Error};
+
+use super::{ErrorCode, ResponseHeaders};
+
+/// An error returned by the report endpoint, optionally referring to a specific transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "xml-response", derive(Deserialize))]
+pub struct ReportError {
+    code: ErrorCode,
+    index: Option<usize>,
+
+    #[cfg_attr(feature = "xml-response", serde(rename = "$value"))]
+    message: Option<String>,
+}
+
+impl ReportError {
+    #[cfg(feature = "xml-response-lite")]
+    pub(super) fn new(code: ErrorCode, index: Option<usize>, message: Option<String>) -> Self {
+        Self {
+            code,
+            index,
+            message,
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        self.code.as_str()
+    }
+
+    pub fn error_code(&self) -> &ErrorCode {
+        &self.code
+    }
+
+    /// The index of the transaction the error refers to, if any.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+#[cfg(not(feature = "xml-response-lite"))]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ErrorDocument {
+    Error(ReportError),
+    Errors {
+        #[serde(rename = "error", default)]
+        errors: Vec<ReportError>,
+    },
+}
+
+/// The response to a report, which Apisonator accepts with a `202 Accepted` status and an empty
+/// body, or rejects with one or more errors.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::{http::HeaderMap, response::*};
+///
+/// let body = r#"<error code="provider_key_invalid">provider key "a" is invalid</error>"#;
+/// let response = ReportResponse::from_parts(403, &HeaderMap::new(), Some(body)).unwrap();
+///
+/// assert!(!response.is_accepted());
+/// assert!(!response.is_retryable());
+/// assert_eq!(response.errors()[0].error_code(), &ErrorCode::ProviderKeyInvalid);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportResponse {
+    status: u16,
+    errors: Vec<ReportError>,
+    headers: ResponseHeaders,
+}
+
+impl ReportResponse {
+    /// Builds the response out of the HTTP status code, headers and body sent by Apisonator.
+    ///
+    /// Bodies of server errors that can't be parsed are ignored, since they are likely to come
+    /// from proxies rather than from Apisonator.
+    pub fn from_parts(status: u16, headers: &HeaderMap, body: Option<&str>) -> Result<Self, Error> {
+        let errors = match body.map(str::trim) {
+            Some(body) if !body.is_empty() => match parse_errors(body) {
+                Ok(errors) => errors,
+                Err(_) if status >= 500 => Vec::new(),
+                Err(e) => return Err(anyhow!("failed to parse report response: {}", e)),
+            },
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            status,
+            errors,
+            headers: ResponseHeaders::from_header_map(headers)?,
+        })
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn errors(&self) -> &[ReportError] {
+        self.errors.as_slice()
+    }
+
+    pub fn headers(&self) -> &ResponseHeaders {
+        &self.headers
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        (200..300).contains(&self.status) && self.errors.is_empty()
+    }
+
+    /// Whether the report failed because of a transient condition, so that sending it again
+    /// could succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.status, 408 | 429 | 500..=599)
+    }
+}
+
+#[cfg(feature = "xml-response-lite")]
+fn parse_errors(body: &str) -> Result<Vec<ReportError>, Error> {
+    super::xml::parse_report_errors(body)
+}
+
+#[cfg(not(feature = "xml-response-lite"))]
+fn parse_errors(body: &str) -> Result<Vec<ReportError>, Error> {
+    match serde_xml_rs::from_str(body) {
+        Ok(ErrorDocument::Error(error)) => Ok(vec![error]),
+        Ok(ErrorDocument::Errors { errors }) => Ok(errors),
+        Err(e) => Err(anyhow!("{}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_empty_responses() {
+        let response = ReportResponse::from_parts(202, &HeaderMap::new(), Some("")).unwrap();
+
+        assert!(response.is_accepted());
+        assert!(!response.is_retryable());
+        assert!(response.errors().is_empty());
+    }
+
+    #[test]
+    fn parses_single_errors() {
+        let body = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <error code="transactions_is_blank">transactions parameter is blank</error>
+        "##;
+
+        let response = ReportResponse::from_parts(400, &HeaderMap::new(), Some(body)).unwrap();
+
+        assert!(!response.is_accepted());
+        assert_eq!(
+            vec![ReportError {
+                code: ErrorCode::TransactionsIsBlank,
+                index: None,
+                message: Some("transactions parameter is blank".into()),
+            }],
+            response.errors()
+        );
+    }
+
+    #[test]
+    fn parses_transaction_errors() {
+        let body = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <errors>
+            <error code="application_not_found" index="0">application with id="a" was not found</error>
+            <error code="usage_value_invalid" index="3">usage value of metric "hits" is invalid</error>
+        </errors>
+        "##;
+
+        let response = ReportResponse::from_parts(202, &HeaderMap::new(), Some(body)).unwrap();
+        let errors = response.errors();
+
+        assert!(!response.is_accepted());
+        assert_eq!(2, errors.len());
+        assert_eq!(&ErrorCode::ApplicationNotFound, errors[0].error_code());
+        assert_eq!(Some(0), errors[0].index());
+        assert_eq!(&ErrorCode::UsageValueInvalid, errors[1].error_code());
+        assert_eq!(Some(3), errors[1].index());
+        assert_eq!(
+            Some(r#"usage value of metric "hits" is invalid"#),
+            errors[1].message()
+        );
+    }
+
+    #[test]
+    fn tells_retryable_failures() {
+        let html = "<html><body>Bad Gateway</body>";
+
+        let response = ReportResponse::from_parts(502, &HeaderMap::new(), Some(html)).unwrap();
+
+        assert!(!response.is_accepted());
+        assert!(response.is_retryable());
+        assert!(ReportResponse::from_parts(400, &HeaderMap::new(), Some(html)).is_err());
+    }
+}
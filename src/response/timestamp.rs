@@ -0,0 +1,236 @@
+use std::prelude::v1::*;
+
+use std::{fmt, str::FromStr};
+
+use crate::{anyhow, Error};
+
+use super::PeriodTime;
+
+/// Parses timestamps in the `%Y-%m-%d %H:%M:%S %z` format used by Apisonator, such as
+/// `2019-06-05 16:24:00 +0000`, without depending on `chrono`.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::response::PeriodTime;
+///
+/// let ts = "2019-06-05 18:24:00 +0200".parse::<PeriodTime>().unwrap();
+///
+/// assert_eq!(ts, PeriodTime(1_559_751_840));
+/// ```
+impl FromStr for PeriodTime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s).ok_or_else(|| anyhow!("invalid timestamp {}, expected %Y-%m-%d %H:%M:%S %z", s))
+    }
+}
+
+/// Formats timestamps in UTC using the `%Y-%m-%d %H:%M:%S %z` format used by Apisonator.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::response::PeriodTime;
+///
+/// assert_eq!(PeriodTime(1_559_751_840).to_string(), "2019-06-05 16:24:00 +0000");
+/// ```
+impl fmt::Display for PeriodTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.0.div_euclid(86400));
+        let seconds = self.0.rem_euclid(86400);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} +0000",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    }
+}
+
+fn parse(s: &str) -> Option<PeriodTime> {
+    let mut parts = s.trim().split(' ').filter(|p| !p.is_empty());
+    let date = parts.next()?;
+    let time = parts.next()?;
+    let offset = parts.next()?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let mut date = date.splitn(3, '-');
+    let year = number(date.next()?, 1, 4)? as i64;
+    let month = number(date.next()?, 2, 2)?;
+    let day = number(date.next()?, 2, 2)?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut time = time.splitn(3, ':');
+    let hour = number(time.next()?, 2, 2)?;
+    let minute = number(time.next()?, 2, 2)?;
+    let second = number(time.next()?, 2, 2)?;
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let offset = utc_offset(offset)?;
+    let seconds = i64::from(hour * 3600 + minute * 60 + second);
+
+    Some(PeriodTime(
+        days_from_civil(year, month, day) * 86400 + seconds - offset,
+    ))
+}
+
+// Parses an unsigned decimal number with a number of digits in the given range.
+fn number(s: &str, min_digits: usize, max_digits: usize) -> Option<u32> {
+    if s.len() < min_digits || s.len() > max_digits || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    s.parse().ok()
+}
+
+// Parses offsets in the `+hhmm` and `+hh:mm` forms into seconds east of UTC.
+fn utc_offset(s: &str) -> Option<i64> {
+    let (sign, hhmm) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    // slicing below relies on each character being a single byte
+    if !hhmm.is_ascii() {
+        return None;
+    }
+
+    let (hours, minutes) = match hhmm.len() {
+        4 => (&hhmm[..2], &hhmm[2..]),
+        5 if hhmm.as_bytes()[2] == b':' => (&hhmm[..2], &hhmm[3..]),
+        _ => return None,
+    };
+    let hours = number(hours, 2, 2)?;
+    let minutes = number(minutes, 2, 2)?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    Some(sign * i64::from(hours * 3600 + minutes * 60))
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Number of days since 1970-01-01 in the proleptic Gregorian calendar, see
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * i64::from((month + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Inverse of days_from_civil, see
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_timestamps() {
+        let parse = |s: &str| s.parse::<PeriodTime>().map(|ts| ts.0).ok();
+
+        assert_eq!(Some(0), parse("1970-01-01 00:00:00 +0000"));
+        assert_eq!(Some(1_559_751_840), parse("2019-06-05 16:24:00 +0000"));
+        assert_eq!(Some(1_559_751_840), parse("2019-06-05 11:54:00 -04:30"));
+        assert_eq!(Some(951_782_400), parse("2000-02-29 00:00:00 +0000"));
+        assert_eq!(Some(-86400), parse(" 1969-12-31 00:00:00 +0000 "));
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        for ts in &[
+            "1970-01-01 00:00:00 +0000",
+            "1969-12-31 23:59:59 +0000",
+            "2000-02-29 12:34:56 +0000",
+            "2019-06-05 16:24:00 +0000",
+            "2100-03-01 00:00:00 +0000",
+        ] {
+            assert_eq!(*ts, ts.parse::<PeriodTime>().unwrap().to_string());
+        }
+
+        let ts = "2019-06-05 11:54:00 -04:30".parse::<PeriodTime>().unwrap();
+        assert_eq!("2019-06-05 16:24:00 +0000", ts.to_string());
+    }
+
+    #[test]
+    fn rejects_invalid_timestamps() {
+        for ts in &[
+            "05-06-2019 16:24:00 +0000",
+            "2019-06-05 16:24:00",
+            "2019-06-05T16:24:00 +0000",
+            "2019-13-05 16:24:00 +0000",
+            "2019-02-29 16:24:00 +0000",
+            "2019-06-05 24:00:00 +0000",
+            "2019-06-05 16:24:00 0000",
+            "2019-06-05 16:24:00 +00000",
+            "2019-06-05 16:24:00 +0000 UTC",
+            "2019-06-05 16:24:00 +0é0",
+        ] {
+            assert!(ts.parse::<PeriodTime>().is_err());
+        }
+    }
+
+    #[cfg(feature = "xml-response")]
+    #[test]
+    fn matches_chrono() {
+        use chrono::DateTime;
+
+        for ts in &[
+            "2019-06-05 16:24:00 +0000",
+            "2020-02-29 23:59:59 +1300",
+            "1900-03-01 12:00:00 -0800",
+            "2038-01-19 03:14:08 +0000",
+        ] {
+            let expected =
+                PeriodTime::from(DateTime::parse_from_str(ts, "%Y-%m-%d %H:%M:%S %z").unwrap());
+
+            assert_eq!(expected, ts.parse::<PeriodTime>().unwrap());
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Conversions between response types and the XML documents sent by Apisonator.
+//!
+//! Serialization is always available. The `xml-response-lite` feature adds a streaming parser
+//! that only requires `alloc` and builds the same types as the `serde` based parser enabled by
+//! the `xml-response` feature. It only knows about the documents Apisonator sends, and ignores
+//! elements it does not expect. When enabled, it is used by `AuthorizationResponse` and
+//! `ReportResponse` to parse response bodies.
+
+#[cfg(feature = "xml-response-lite")]
+mod parser;
+#[cfg(feature = "xml-response-lite")]
+pub(super) use parser::parse_report_errors;
+#[cfg(feature = "xml-response-lite")]
+pub use parser::{parse_app_keys, parse_authorization, parse_hierarchy, parse_usage_reports};
+
+#[cfg(feature = "xml-response-lite")]
+mod reader;
+
+mod writer;
+pub use writer::{to_string, ToXml};
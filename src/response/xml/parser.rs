@@ -0,0 +1,548 @@
+use std::prelude::v1::*;
+
+use std::{borrow::Cow, fmt::Display, str::FromStr};
+
+use crate::{anyhow, Error};
+
+use crate::response::{
+    AppKeysList, Authorization, AuthorizationError, AuthorizationStatus, MetricsHierarchy,
+    OAuthApplication, ReportError, UsageReport, UsageReports,
+};
+
+use super::reader::{Event, Reader, Start};
+
+/// Parses the response of the authorization endpoints, either a `status` or an `error`.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::response::{xml, Authorization, ErrorCode};
+///
+/// let body = r#"<error code="user_key_invalid">user key "a" is invalid</error>"#;
+///
+/// match xml::parse_authorization(body).unwrap() {
+///     Authorization::Error(e) => assert_eq!(e.error_code(), &ErrorCode::UserKeyInvalid),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn parse_authorization(xml: &str) -> Result<Authorization, Error> {
+    document(xml, |reader, start| match start.name() {
+        "status" => read_status(reader).map(Authorization::Status),
+        "error" => read_error(reader, start).map(Authorization::Error),
+        name => Err(anyhow!("unexpected root element {}", name)),
+    })
+}
+
+/// Parses a `usage_reports` document.
+pub fn parse_usage_reports(xml: &str) -> Result<UsageReports, Error> {
+    document(xml, |reader, start| {
+        expect_root(start, "usage_reports")?;
+        read_usage_reports(reader)
+    })
+}
+
+/// Parses a `hierarchy` document.
+pub fn parse_hierarchy(xml: &str) -> Result<MetricsHierarchy, Error> {
+    document(xml, |reader, start| {
+        expect_root(start, "hierarchy")?;
+        read_hierarchy(reader)
+    })
+}
+
+/// Parses an `app_keys` document.
+pub fn parse_app_keys(xml: &str) -> Result<AppKeysList, Error> {
+    document(xml, |reader, start| {
+        expect_root(start, "app_keys")?;
+        read_app_keys(reader, start)
+    })
+}
+
+/// Parses the errors returned by the report endpoint, either a single `error` or a list of them
+/// wrapped in `errors`.
+pub(in crate::response) fn parse_report_errors(xml: &str) -> Result<Vec<ReportError>, Error> {
+    document(xml, |reader, start| match start.name() {
+        "error" => read_report_error(reader, start).map(|e| vec![e]),
+        "errors" => {
+            let mut errors = Vec::new();
+            children(reader, |reader, start| match start.name() {
+                "error" => read_report_error(reader, start).map(|e| errors.push(e)),
+                _ => skip(reader),
+            })?;
+            Ok(errors)
+        }
+        name => Err(anyhow!("unexpected root element {}", name)),
+    })
+}
+
+fn read_status(reader: &mut Reader<'_>) -> Result<AuthorizationStatus, Error> {
+    let mut authorized = None;
+    let mut reason = None;
+    let mut plan = None;
+    let mut usage_reports = None;
+    let mut metrics_hierarchy = None;
+    let mut app_keys = None;
+    let mut application = None;
+
+    children(reader, |reader, start| {
+        match start.name() {
+            "authorized" => authorized = Some(parse_text(reader, "authorized")?),
+            "reason" => reason = Some(text(reader)?.into_owned()),
+            "plan" => plan = Some(text(reader)?.into_owned()),
+            "usage_reports" => usage_reports = Some(read_usage_reports(reader)?),
+            "hierarchy" => metrics_hierarchy = Some(read_hierarchy(reader)?),
+            "app_keys" => app_keys = Some(read_app_keys(reader, start)?),
+            "application" => application = Some(Box::new(read_application(reader)?)),
+            _ => skip(reader)?,
+        }
+        Ok(())
+    })?;
+
+    Ok(AuthorizationStatus {
+        authorized: authorized.ok_or_else(|| missing("authorized", "status"))?,
+        reason,
+        plan: plan.ok_or_else(|| missing("plan", "status"))?,
+        usage_reports,
+        metrics_hierarchy,
+        app_keys,
+        application,
+    })
+}
+
+fn read_error(reader: &mut Reader<'_>, start: Start<'_>) -> Result<AuthorizationError, Error> {
+    let code = required_attribute(start, "code")?;
+
+    Ok(AuthorizationError {
+        code: code.into_owned().into(),
+        message: optional_text(reader)?,
+    })
+}
+
+fn read_report_error(reader: &mut Reader<'_>, start: Start<'_>) -> Result<ReportError, Error> {
+    let code = required_attribute(start, "code")?;
+    let index = start
+        .attribute("index")?
+        .map(|index| parse_value(&index, "index"))
+        .transpose()?;
+
+    Ok(ReportError::new(
+        code.into_owned().into(),
+        index,
+        optional_text(reader)?,
+    ))
+}
+
+fn read_usage_reports(reader: &mut Reader<'_>) -> Result<UsageReports, Error> {
+    let mut reports = Vec::new();
+
+    children(reader, |reader, start| match start.name() {
+        "usage_report" => read_usage_report(reader, start).map(|r| reports.push(r)),
+        _ => skip(reader),
+    })?;
+
+    Ok(UsageReports::UsageReports(reports))
+}
+
+fn read_usage_report(reader: &mut Reader<'_>, start: Start<'_>) -> Result<UsageReport, Error> {
+    let metric = required_attribute(start, "metric")?.into_owned();
+    let period = parse_value(&required_attribute(start, "period")?, "period")?;
+    let mut period_start = None;
+    let mut period_end = None;
+    let mut max_value = None;
+    let mut current_value = None;
+
+    children(reader, |reader, start| {
+        match start.name() {
+            "period_start" => period_start = Some(parse_text(reader, "period_start")?),
+            "period_end" => period_end = Some(parse_text(reader, "period_end")?),
+            "max_value" => max_value = Some(parse_text(reader, "max_value")?),
+            "current_value" => current_value = Some(parse_text(reader, "current_value")?),
+            _ => skip(reader)?,
+        }
+        Ok(())
+    })?;
+
+    Ok(UsageReport {
+        metric,
+        period,
+        period_start: period_start.ok_or_else(|| missing("period_start", "usage_report"))?,
+        period_end: period_end.ok_or_else(|| missing("period_end", "usage_report"))?,
+        max_value: max_value.ok_or_else(|| missing("max_value", "usage_report"))?,
+        current_value: current_value.ok_or_else(|| missing("current_value", "usage_report"))?,
+    })
+}
+
+fn read_hierarchy(reader: &mut Reader<'_>) -> Result<MetricsHierarchy, Error> {
+    let mut hierarchy = MetricsHierarchy::new();
+
+    children(reader, |reader, start| {
+        if start.name() == "metric" {
+            let parent_metric = required_attribute(start, "name")?;
+            let children_metrics = required_attribute(start, "children")?
+                .split(' ')
+                .map(|s| s.to_owned())
+                .collect::<Vec<_>>();
+
+            hierarchy.insert(parent_metric.into_owned(), children_metrics);
+        }
+
+        skip(reader)
+    })?;
+
+    Ok(hierarchy)
+}
+
+fn read_app_keys(reader: &mut Reader<'_>, start: Start<'_>) -> Result<AppKeysList, Error> {
+    let service_id = start.attribute("svc")?.map(Cow::into_owned);
+    let app_id = start.attribute("app")?.map(Cow::into_owned);
+    // the usual maximum capacity is 5 entries
+    let mut keys = Vec::with_capacity(5);
+
+    children(reader, |reader, start| {
+        if start.name() == "key" {
+            keys.push(required_attribute(start, "id")?.into_owned());
+        }
+
+        skip(reader)
+    })?;
+
+    Ok(AppKeysList::new(service_id, app_id, keys))
+}
+
+fn read_application(reader: &mut Reader<'_>) -> Result<OAuthApplication, Error> {
+    let mut id = None;
+    let mut key = None;
+    let mut redirect_url = None;
+
+    children(reader, |reader, start| {
+        match start.name() {
+            "id" => id = Some(text(reader)?.into_owned()),
+            "key" => key = Some(text(reader)?.into_owned()),
+            "redirect_url" => redirect_url = Some(text(reader)?.into_owned()),
+            _ => skip(reader)?,
+        }
+        Ok(())
+    })?;
+
+    Ok(OAuthApplication::new(
+        id.ok_or_else(|| missing("id", "application"))?,
+        key,
+        redirect_url,
+    ))
+}
+
+// Reads a whole document, calling `f` with its root element, which must be completely consumed.
+fn document<'a, T>(
+    xml: &'a str,
+    f: impl FnOnce(&mut Reader<'a>, Start<'a>) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let mut reader = Reader::new(xml);
+
+    let value = match reader.next()? {
+        Event::Start(start) => f(&mut reader, start)?,
+        _ => return Err(anyhow!("missing root element")),
+    };
+
+    match reader.next()? {
+        Event::Eof => Ok(value),
+        _ => Err(anyhow!("unexpected content after the root element")),
+    }
+}
+
+fn expect_root(start: Start<'_>, name: &str) -> Result<(), Error> {
+    if start.name() == name {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "unexpected root element {}, expected {}",
+            start.name(),
+            name
+        ))
+    }
+}
+
+// Calls `f` with each child element of the current one, which must completely consume it. Text
+// between child elements is ignored.
+fn children<'a>(
+    reader: &mut Reader<'a>,
+    mut f: impl FnMut(&mut Reader<'a>, Start<'a>) -> Result<(), Error>,
+) -> Result<(), Error> {
+    loop {
+        match reader.next()? {
+            Event::Start(start) => f(reader, start)?,
+            Event::Text(_) => (),
+            Event::End => return Ok(()),
+            Event::Eof => return Err(anyhow!("unexpected end of document")),
+        }
+    }
+}
+
+// Skips the rest of the current element.
+fn skip(reader: &mut Reader<'_>) -> Result<(), Error> {
+    let mut depth = 1usize;
+
+    loop {
+        match reader.next()? {
+            Event::Start(_) => depth += 1,
+            Event::Text(_) => (),
+            Event::End => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+            Event::Eof => return Err(anyhow!("unexpected end of document")),
+        }
+    }
+}
+
+// Reads the text content of the current element, which must not contain other elements.
+fn text<'a>(reader: &mut Reader<'a>) -> Result<Cow<'a, str>, Error> {
+    let mut text = Cow::Borrowed("");
+
+    loop {
+        match reader.next()? {
+            Event::Text(t) if text.is_empty() => text = t,
+            Event::Text(t) => text.to_mut().push_str(&t),
+            Event::End => return Ok(text),
+            Event::Start(start) => {
+                return Err(anyhow!("unexpected element {} in text", start.name()))
+            }
+            Event::Eof => return Err(anyhow!("unexpected end of document")),
+        }
+    }
+}
+
+fn optional_text(reader: &mut Reader<'_>) -> Result<Option<String>, Error> {
+    let text = text(reader)?;
+
+    Ok(if text.is_empty() {
+        None
+    } else {
+        Some(text.into_owned())
+    })
+}
+
+fn parse_text<T>(reader: &mut Reader<'_>, name: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    parse_value(&text(reader)?, name)
+}
+
+fn parse_value<T>(value: &str, name: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| anyhow!("invalid {} {:?}: {}", name, value, e))
+}
+
+fn required_attribute<'a>(start: Start<'a>, name: &str) -> Result<Cow<'a, str>, Error> {
+    start
+        .attribute(name)?
+        .ok_or_else(|| missing(name, start.name()))
+}
+
+fn missing(field: &str, element: &str) -> Error {
+    anyhow!("missing {} in {}", field, element)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{ErrorCode, Period, PeriodTime};
+
+    const STATUS: &str = r##"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <status>
+            <authorized>false</authorized>
+            <reason>usage limits are exceeded</reason>
+            <plan>App &amp; Plan</plan>
+            <usage_reports>
+                <usage_report metric="hits" period="minute" exceeded="true">
+                    <period_start>2019-06-05 16:24:00 +0000</period_start>
+                    <period_end>2019-06-05 16:25:00 +0000</period_end>
+                    <max_value>5</max_value>
+                    <current_value>5</current_value>
+                </usage_report>
+                <usage_report metric="hits" period="eternity">
+                    <max_value>50</max_value>
+                    <current_value>5</current_value>
+                    <period_start>1970-01-01 00:00:00 +0000</period_start>
+                    <period_end>1970-01-01 00:00:00 +0000</period_end>
+                </usage_report>
+            </usage_reports>
+            <hierarchy>
+                <metric name="hits" children="products sales"/>
+            </hierarchy>
+            <app_keys app="app_id" svc="service_id">
+                <key id="key_1"/>
+                <key id="key_2"></key>
+            </app_keys>
+            <application>
+                <id>app_id</id>
+                <key>key_1</key>
+                <redirect_url>https://example.com/callback?a=1&amp;b=2</redirect_url>
+            </application>
+        </status>
+    "##;
+
+    // Documents also accepted by the serde based parser, used to check that both agree.
+    const AUTHORIZATIONS: &[&str] = &[
+        STATUS,
+        r#"<status><authorized>true</authorized><plan>Basic</plan></status>"#,
+        r#"<status><plan>Basic</plan><authorized>true</authorized><extra><a/></extra></status>"#,
+        r#"<error code="user_key_invalid">user key "a" is invalid</error>"#,
+        r#"<error code="user_key_invalid">user key &quot;a&quot; is &lt;invalid&gt;</error>"#,
+        r#"<error code="user_key_invalid"><![CDATA[user key "a" is <invalid>]]></error>"#,
+        r#"<error code="some_new_error"/>"#,
+    ];
+
+    #[test]
+    fn parses_statuses() {
+        let status = match parse_authorization(STATUS).unwrap() {
+            Authorization::Status(status) => status,
+            _ => unreachable!(),
+        };
+
+        assert!(!status.authorized());
+        assert_eq!(Some("usage limits are exceeded"), status.reason());
+        assert_eq!("App & Plan", status.plan());
+
+        let reports = status.usage_reports().unwrap().reports();
+        assert_eq!(2, reports.len());
+        assert_eq!(
+            UsageReport {
+                metric: "hits".into(),
+                period: Period::Minute,
+                period_start: PeriodTime(1_559_751_840),
+                period_end: PeriodTime(1_559_751_900),
+                max_value: 5,
+                current_value: 5,
+            },
+            reports[0]
+        );
+        assert_eq!(Period::Eternity, reports[1].period);
+
+        let hierarchy = status.hierarchy().unwrap();
+        assert_eq!(Some("hits"), hierarchy.parent_of("sales"));
+
+        let app_keys = status.app_keys().unwrap();
+        assert_eq!("service_id", app_keys.service_id().unwrap().as_ref());
+        assert_eq!("app_id", app_keys.app_id().unwrap().as_ref());
+        assert_eq!(2, app_keys.keys().len());
+
+        let application = status.application().unwrap();
+        assert_eq!("app_id", application.id());
+        assert_eq!(
+            Some("https://example.com/callback?a=1&b=2"),
+            application.redirect_url()
+        );
+    }
+
+    #[test]
+    fn parses_errors() {
+        for (body, message) in &[
+            (AUTHORIZATIONS[3], Some(r#"user key "a" is invalid"#)),
+            (AUTHORIZATIONS[4], Some(r#"user key "a" is <invalid>"#)),
+            (AUTHORIZATIONS[5], Some(r#"user key "a" is <invalid>"#)),
+            (r#"<error code="user_key_invalid"></error>"#, None),
+        ] {
+            match parse_authorization(body).unwrap() {
+                Authorization::Error(e) => {
+                    assert_eq!(&ErrorCode::UserKeyInvalid, e.error_code());
+                    assert_eq!(*message, e.message());
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_report_errors() {
+        let body = r#"<errors>
+            <error code="application_not_found" index="0">application not found</error>
+            <error code="usage_value_invalid" index="3"/>
+        </errors>"#;
+
+        let errors = parse_report_errors(body).unwrap();
+
+        assert_eq!(2, errors.len());
+        assert_eq!(Some(3), errors[1].index());
+        assert_eq!(None, errors[1].message());
+        assert_eq!(
+            &ErrorCode::ApplicationNotFound,
+            parse_report_errors(r#"<error code="application_not_found"/>"#).unwrap()[0]
+                .error_code()
+        );
+    }
+
+    #[test]
+    fn parses_standalone_documents() {
+        let reports = parse_usage_reports(
+            r#"<usage_reports><usage_report metric="hits" period="day">
+                <period_start>2019-06-05 00:00:00 +0000</period_start>
+                <period_end>2019-06-06 00:00:00 +0000</period_end>
+                <max_value>10</max_value><current_value>1</current_value>
+            </usage_report></usage_reports>"#,
+        )
+        .unwrap();
+        assert_eq!(Some(9), reports.remaining_for_metric("hits"));
+
+        let hierarchy =
+            parse_hierarchy(r#"<hierarchy><metric name="a" children="b"/></hierarchy>"#);
+        assert_eq!(Some("a"), hierarchy.unwrap().parent_of("b"));
+
+        let app_keys = parse_app_keys(r#"<app_keys app="a"/>"#).unwrap();
+        assert!(app_keys.service_id().is_none());
+        assert!(app_keys.keys().is_empty());
+
+        assert!(parse_usage_reports("<usage_reports/>")
+            .unwrap()
+            .reports()
+            .is_empty());
+        assert!(parse_hierarchy("<app_keys/>").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        for body in &[
+            "",
+            "<status><authorized>true</authorized></status>",
+            "<status><authorized>maybe</authorized><plan>a</plan></status>",
+            "<status><authorized>true</authorized><plan><b/></plan></status>",
+            "<status><authorized>true</authorized><plan>a</plan>",
+            "<error>no code</error>",
+            "<errors/>",
+            r#"<status><authorized>true</authorized><plan>a</plan><usage_reports>
+                <usage_report metric="hits" period="fortnight"/>
+            </usage_reports></status>"#,
+        ] {
+            assert!(parse_authorization(body).is_err());
+        }
+    }
+
+    #[test]
+    fn roundtrips_serialized_documents() {
+        for body in AUTHORIZATIONS {
+            let authorization = parse_authorization(body).unwrap();
+            let xml = crate::response::xml::to_string(&authorization);
+
+            assert_eq!(authorization, parse_authorization(&xml).unwrap());
+        }
+    }
+
+    #[cfg(feature = "xml-response")]
+    #[test]
+    fn agrees_with_serde_parser() {
+        for body in AUTHORIZATIONS {
+            let expected: Authorization = serde_xml_rs::from_str(body).unwrap();
+
+            assert_eq!(expected, parse_authorization(body).unwrap());
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use std::prelude::v1::*;
+
+use std::borrow::Cow;
+
+use crate::{anyhow, Error};
+
+/// A start tag, with its attributes kept unparsed until they are looked up.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Start<'a> {
+    name: &'a str,
+    attributes: &'a str,
+}
+
+impl<'a> Start<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn attributes(&self) -> Attributes<'a> {
+        Attributes {
+            input: self.attributes,
+        }
+    }
+
+    /// Looks up the unescaped value of an attribute.
+    pub fn attribute(&self, name: &str) -> Result<Option<Cow<'a, str>>, Error> {
+        for attribute in self.attributes() {
+            let (key, value) = attribute?;
+            if key == name {
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+pub(super) struct Attributes<'a> {
+    input: &'a str,
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = Result<(&'a str, Cow<'a, str>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.input.trim_start();
+        if input.is_empty() {
+            return None;
+        }
+
+        let attribute = (|| {
+            let eq = input
+                .find('=')
+                .ok_or_else(|| anyhow!("missing value in attribute {:?}", input))?;
+            let key = input[..eq].trim_end();
+            if !is_name(key) {
+                return Err(anyhow!("invalid attribute name {:?}", key));
+            }
+
+            let rest = input[eq + 1..].trim_start();
+            let quote = match rest.chars().next() {
+                Some(q @ '"') | Some(q @ '\'') => q,
+                _ => return Err(anyhow!("unquoted value in attribute {:?}", key)),
+            };
+            let end = rest[1..]
+                .find(quote)
+                .ok_or_else(|| anyhow!("unterminated value in attribute {:?}", key))?;
+            let value = unescape(&rest[1..=end])?;
+
+            Ok((key, value, &rest[end + 2..]))
+        })();
+
+        Some(match attribute {
+            Ok((key, value, rest)) => {
+                self.input = rest;
+                Ok((key, value))
+            }
+            Err(e) => {
+                // stop iterating after an error
+                self.input = "";
+                Err(e)
+            }
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum Event<'a> {
+    Start(Start<'a>),
+    End,
+    /// Character data, with entities unescaped. Contents of CDATA sections are returned as is.
+    Text(Cow<'a, str>),
+    Eof,
+}
+
+/// A pull parser for the small subset of XML used in Apisonator responses.
+///
+/// It borrows from the input whenever possible, and only keeps the stack of open element names.
+/// Declarations, processing instructions, comments and DTDs are skipped.
+pub(super) struct Reader<'a> {
+    input: &'a str,
+    pos: usize,
+    open: Vec<&'a str>,
+    // set when reading an empty element tag, so that its end event is returned next
+    pending_end: bool,
+    root_closed: bool,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            open: Vec::with_capacity(8),
+            pending_end: false,
+            root_closed: false,
+        }
+    }
+
+    pub fn next(&mut self) -> Result<Event<'a>, Error> {
+        if self.pending_end {
+            self.pending_end = false;
+            // an empty element tag always pushes its name
+            self.open.pop();
+            self.root_closed = self.open.is_empty();
+            return Ok(Event::End);
+        }
+
+        loop {
+            let rest = &self.input[self.pos..];
+
+            if rest.is_empty() {
+                return match self.open.last() {
+                    Some(name) => Err(anyhow!("unexpected end of document in element {}", name)),
+                    None => Ok(Event::Eof),
+                };
+            }
+
+            if !rest.starts_with('<') {
+                let len = rest.find('<').unwrap_or(rest.len());
+                let text = &rest[..len];
+                self.pos += len;
+
+                if self.open.is_empty() {
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    return Err(self.error("text outside of the root element"));
+                }
+
+                return unescape(text).map(Event::Text);
+            }
+
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                let start = self.pos + "<![CDATA[".len();
+                self.skip_past("]]>")?;
+                return Ok(Event::Text(Cow::Borrowed(
+                    &self.input[start..self.pos - "]]>".len()],
+                )));
+            } else if rest.starts_with("<!") {
+                // DOCTYPE declarations without an internal subset
+                self.skip_past(">")?;
+            } else if rest.starts_with("</") {
+                return self.end_tag();
+            } else {
+                return self.start_tag();
+            }
+        }
+    }
+
+    fn start_tag(&mut self) -> Result<Event<'a>, Error> {
+        let tag_start = self.pos + 1;
+        let len = tag_len(&self.input[tag_start..])
+            .ok_or_else(|| self.error("unterminated start tag"))?;
+        let tag = &self.input[tag_start..tag_start + len];
+        self.pos = tag_start + len + 1;
+
+        // str::strip_suffix is not available in our MSRV
+        #[allow(clippy::manual_strip)]
+        let (tag, empty) = if tag.ends_with('/') {
+            (&tag[..tag.len() - 1], true)
+        } else {
+            (tag, false)
+        };
+        let name_len = tag
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(tag.len());
+        let name = &tag[..name_len];
+        if !is_name(name) {
+            return Err(anyhow!("invalid element name {:?}", name));
+        }
+        if self.root_closed {
+            return Err(anyhow!(
+                "unexpected element {} after the root element",
+                name
+            ));
+        }
+
+        self.open.push(name);
+        self.pending_end = empty;
+
+        Ok(Event::Start(Start {
+            name,
+            attributes: &tag[name_len..],
+        }))
+    }
+
+    fn end_tag(&mut self) -> Result<Event<'a>, Error> {
+        let tag_start = self.pos + 2;
+        let len = self.input[tag_start..]
+            .find('>')
+            .ok_or_else(|| self.error("unterminated end tag"))?;
+        let name = self.input[tag_start..tag_start + len].trim_end();
+        self.pos = tag_start + len + 1;
+
+        match self.open.pop() {
+            Some(open) if open == name => {
+                self.root_closed = self.open.is_empty();
+                Ok(Event::End)
+            }
+            Some(open) => Err(anyhow!(
+                "mismatched end tag, expected {} but found {}",
+                open,
+                name
+            )),
+            None => Err(anyhow!("unexpected end tag {}", name)),
+        }
+    }
+
+    fn skip_past(&mut self, delimiter: &str) -> Result<(), Error> {
+        match self.input[self.pos..].find(delimiter) {
+            Some(idx) => {
+                self.pos += idx + delimiter.len();
+                Ok(())
+            }
+            None => Err(anyhow!(
+                "unexpected end of document, expected {:?}",
+                delimiter
+            )),
+        }
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        anyhow!("{} at byte {}", msg, self.pos)
+    }
+}
+
+// Finds the end of a start tag, skipping over any '>' found in attribute values.
+fn tag_len(s: &str) -> Option<usize> {
+    let mut quote = None;
+
+    s.char_indices().find_map(|(idx, c)| {
+        match (quote, c) {
+            (None, '>') => return Some(idx),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            _ => (),
+        }
+        None
+    })
+}
+
+fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == ':' => {
+            chars.all(|c| c.is_alphanumeric() || c == '_' || c == ':' || c == '-' || c == '.')
+        }
+        _ => false,
+    }
+}
+
+/// Replaces the predefined and character entities, borrowing the input if there are none.
+pub(super) fn unescape(s: &str) -> Result<Cow<'_, str>, Error> {
+    let first = match s.find('&') {
+        Some(idx) => idx,
+        None => return Ok(Cow::Borrowed(s)),
+    };
+
+    let mut unescaped = String::with_capacity(s.len());
+    unescaped.push_str(&s[..first]);
+    let mut rest = &s[first..];
+
+    while let Some(idx) = rest.find('&') {
+        unescaped.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+
+        let end = rest
+            .find(';')
+            .ok_or_else(|| anyhow!("unterminated entity in {:?}", s))?;
+        let entity = &rest[..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ if entity.starts_with("#x") => char_ref(&entity[2..], 16)?,
+            _ if entity.starts_with('#') => char_ref(&entity[1..], 10)?,
+            _ => return Err(anyhow!("unknown entity &{};", entity)),
+        };
+        unescaped.push(c);
+        rest = &rest[end + 1..];
+    }
+    unescaped.push_str(rest);
+
+    Ok(Cow::Owned(unescaped))
+}
+
+fn char_ref(digits: &str, radix: u32) -> Result<char, Error> {
+    u32::from_str_radix(digits, radix)
+        .ok()
+        .and_then(std::char::from_u32)
+        .ok_or_else(|| anyhow!("invalid character reference {:?}", digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(input: &str) -> Result<Vec<String>, Error> {
+        let mut reader = Reader::new(input);
+        let mut events = Vec::new();
+
+        loop {
+            let event = match reader.next()? {
+                Event::Start(start) => {
+                    let attributes = start
+                        .attributes()
+                        .map(|a| a.map(|(k, v)| format!(" {}={}", k, v)))
+                        .collect::<Result<String, _>>()?;
+                    format!("<{}{}>", start.name(), attributes)
+                }
+                Event::End => "</>".to_owned(),
+                Event::Text(text) => text.into_owned(),
+                Event::Eof => return Ok(events),
+            };
+            events.push(event);
+        }
+    }
+
+    #[test]
+    fn reads_events() {
+        let input = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!-- comment -->
+            <a x="1" y = 'two &amp; three > two'><b/>text &lt;1&gt;<![CDATA[<raw>]]></a>
+        "#;
+
+        assert_eq!(
+            vec![
+                "<a x=1 y=two & three > two>",
+                "<b>",
+                "</>",
+                "text <1>",
+                "<raw>",
+                "</>"
+            ],
+            events(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        for input in &[
+            "<a>",
+            "<a></b>",
+            "</a>",
+            "<a></a><b></b>",
+            "text<a></a>",
+            "<a x=1></a>",
+            "<a x=\"1></a>",
+            "<a>&unknown;</a>",
+            "<a>&#xD800;</a>",
+            "<a><!-- unterminated </a>",
+            "<1a></1a>",
+        ] {
+            assert!(events(input).is_err());
+        }
+    }
+
+    #[test]
+    fn unescapes_character_references() {
+        assert_eq!("a\"b'c", unescape("a&quot;b&apos;c").unwrap());
+        assert_eq!("é€", unescape("&#233;&#x20AC;").unwrap());
+        assert!(matches!(unescape("plain").unwrap(), Cow::Borrowed("plain")));
+    }
+}
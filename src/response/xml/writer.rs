@@ -0,0 +1,278 @@
+use std::prelude::v1::*;
+
+use std::fmt::{self, Write};
+
+use crate::response::{
+    AppKeysList, Authorization, AuthorizationError, AuthorizationStatus, MetricsHierarchy,
+    OAuthApplication, UsageReport, UsageReports,
+};
+
+const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+/// Types that can be written as the XML elements sent by Apisonator.
+pub trait ToXml {
+    /// Writes the element representing this value, without an XML declaration.
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result;
+}
+
+/// Serializes a value into a document with the same layout Apisonator uses, including the XML
+/// declaration.
+///
+/// # Examples
+///
+/// ```
+/// use threescalers::response::{xml, Authorization};
+///
+/// let body = r#"<?xml version="1.0" encoding="UTF-8"?><error code="user_key_invalid">user key "a" is invalid</error>"#;
+/// let authorization = body.parse::<Authorization>().unwrap();
+///
+/// assert_eq!(
+///     xml::to_string(&authorization),
+///     r#"<?xml version="1.0" encoding="UTF-8"?><error code="user_key_invalid">user key &quot;a&quot; is invalid</error>"#
+/// );
+/// ```
+pub fn to_string<T: ToXml + ?Sized>(value: &T) -> String {
+    let mut xml = String::from(XML_DECLARATION);
+    // writing to a String never fails
+    let _ = value.write_xml(&mut xml);
+
+    xml
+}
+
+impl ToXml for Authorization {
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result {
+        match self {
+            Authorization::Status(status) => status.write_xml(writer),
+            Authorization::Error(error) => error.write_xml(writer),
+        }
+    }
+}
+
+impl ToXml for AuthorizationStatus {
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str("<status>")?;
+        element(writer, "authorized", self.authorized)?;
+        if let Some(reason) = &self.reason {
+            element(writer, "reason", Escaped(reason))?;
+        }
+        if let Some(application) = &self.application {
+            application.write_xml(writer)?;
+        }
+        element(writer, "plan", Escaped(&self.plan))?;
+        if let Some(usage_reports) = &self.usage_reports {
+            usage_reports.write_xml(writer)?;
+        }
+        if let Some(hierarchy) = &self.metrics_hierarchy {
+            hierarchy.write_xml(writer)?;
+        }
+        if let Some(app_keys) = &self.app_keys {
+            app_keys.write_xml(writer)?;
+        }
+        writer.write_str("</status>")
+    }
+}
+
+impl ToXml for AuthorizationError {
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result {
+        write!(writer, r#"<error code="{}""#, Escaped(self.code.as_str()))?;
+        match &self.message {
+            Some(message) => write!(writer, ">{}</error>", Escaped(message)),
+            None => writer.write_str("/>"),
+        }
+    }
+}
+
+impl ToXml for UsageReports {
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str("<usage_reports>")?;
+        for report in self.iter() {
+            report.write_xml(writer)?;
+        }
+        writer.write_str("</usage_reports>")
+    }
+}
+
+impl ToXml for UsageReport {
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result {
+        write!(
+            writer,
+            r#"<usage_report metric="{}" period="{}""#,
+            Escaped(&self.metric),
+            self.period
+        )?;
+        if self.current_value > self.max_value {
+            writer.write_str(r#" exceeded="true""#)?;
+        }
+        writer.write_char('>')?;
+        element(writer, "period_start", &self.period_start)?;
+        element(writer, "period_end", &self.period_end)?;
+        element(writer, "max_value", self.max_value)?;
+        element(writer, "current_value", self.current_value)?;
+        writer.write_str("</usage_report>")
+    }
+}
+
+impl ToXml for MetricsHierarchy {
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str("<hierarchy>")?;
+        for (parent, children) in self.iter() {
+            write!(writer, r#"<metric name="{}" children=""#, Escaped(parent))?;
+            for (idx, child) in children.iter().enumerate() {
+                if idx > 0 {
+                    writer.write_char(' ')?;
+                }
+                write!(writer, "{}", Escaped(child))?;
+            }
+            writer.write_str(r#""/>"#)?;
+        }
+        writer.write_str("</hierarchy>")
+    }
+}
+
+impl ToXml for AppKeysList {
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str("<app_keys")?;
+        if let Some(app_id) = self.app_id() {
+            write!(writer, r#" app="{}""#, Escaped(app_id.as_ref()))?;
+        }
+        if let Some(service_id) = self.service_id() {
+            write!(writer, r#" svc="{}""#, Escaped(service_id.as_ref()))?;
+        }
+        writer.write_char('>')?;
+        for key in self.keys() {
+            write!(writer, r#"<key id="{}"/>"#, Escaped(key.as_ref()))?;
+        }
+        writer.write_str("</app_keys>")
+    }
+}
+
+impl ToXml for OAuthApplication {
+    fn write_xml<W: Write>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str("<application>")?;
+        element(writer, "id", Escaped(&self.id))?;
+        if let Some(key) = &self.key {
+            element(writer, "key", Escaped(key))?;
+        }
+        if let Some(redirect_url) = &self.redirect_url {
+            element(writer, "redirect_url", Escaped(redirect_url))?;
+        }
+        writer.write_str("</application>")
+    }
+}
+
+fn element<W: Write, T: fmt::Display>(writer: &mut W, name: &str, value: T) -> fmt::Result {
+    write!(writer, "<{}>{}</{}>", name, value, name)
+}
+
+// Escapes text for use both in elements and in double-quoted attributes.
+struct Escaped<'a>(&'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.0;
+
+        while let Some(idx) = rest.find(&['&', '<', '>', '"'][..]) {
+            f.write_str(&rest[..idx])?;
+            f.write_str(match rest.as_bytes()[idx] {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                _ => "&quot;",
+            })?;
+            rest = &rest[idx + 1..];
+        }
+
+        f.write_str(rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{ErrorCode, Period, PeriodTime};
+
+    fn status() -> AuthorizationStatus {
+        let mut hierarchy = MetricsHierarchy::new();
+        hierarchy.insert("hits", vec!["products".to_owned(), "sales".to_owned()]);
+
+        AuthorizationStatus {
+            authorized: false,
+            reason: Some("usage limits are exceeded".into()),
+            plan: "Gold & Silver".into(),
+            usage_reports: Some(UsageReports::UsageReports(vec![UsageReport {
+                metric: "hits".into(),
+                period: Period::Minute,
+                period_start: PeriodTime(1_559_751_840),
+                period_end: PeriodTime(1_559_751_900),
+                max_value: 5,
+                current_value: 6,
+            }])),
+            metrics_hierarchy: Some(hierarchy),
+            app_keys: Some(AppKeysList::new(
+                Some("svc"),
+                Some("app"),
+                vec!["key_1", "key\"2"],
+            )),
+            application: Some(Box::new(OAuthApplication::new(
+                "app",
+                Some("key_1"),
+                Some("https://example.com/?a=1&b=2"),
+            ))),
+        }
+    }
+
+    #[test]
+    fn writes_statuses() {
+        let expected = concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "<status>",
+            "<authorized>false</authorized>",
+            "<reason>usage limits are exceeded</reason>",
+            "<application><id>app</id><key>key_1</key>",
+            "<redirect_url>https://example.com/?a=1&amp;b=2</redirect_url></application>",
+            "<plan>Gold &amp; Silver</plan>",
+            r#"<usage_reports><usage_report metric="hits" period="minute" exceeded="true">"#,
+            "<period_start>2019-06-05 16:24:00 +0000</period_start>",
+            "<period_end>2019-06-05 16:25:00 +0000</period_end>",
+            "<max_value>5</max_value><current_value>6</current_value>",
+            "</usage_report></usage_reports>",
+            r#"<hierarchy><metric name="hits" children="products sales"/></hierarchy>"#,
+            r#"<app_keys app="app" svc="svc"><key id="key_1"/><key id="key&quot;2"/></app_keys>"#,
+            "</status>",
+        );
+
+        assert_eq!(expected, to_string(&Authorization::Status(status())));
+    }
+
+    #[test]
+    fn writes_errors() {
+        let error = AuthorizationError {
+            code: ErrorCode::Unknown("some_new_error".into()),
+            message: None,
+        };
+
+        assert_eq!(
+            r#"<error code="some_new_error"/>"#,
+            &to_string(&error)[XML_DECLARATION.len()..]
+        );
+    }
+
+    #[cfg(feature = "xml-response-lite")]
+    #[test]
+    fn roundtrips_with_lite_parser() {
+        let status = Authorization::Status(status());
+
+        assert_eq!(
+            status,
+            super::super::parse_authorization(&to_string(&status)).unwrap()
+        );
+    }
+
+    #[cfg(feature = "xml-response")]
+    #[test]
+    fn roundtrips_with_serde_parser() {
+        let status = Authorization::Status(status());
+
+        assert_eq!(status, to_string(&status).parse::<Authorization>().unwrap());
+    }
+}
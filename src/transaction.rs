@@ -1,13 +1,50 @@
 use std::prelude::v1::*;
 
-use super::{application::Application, usage::Usage, user::User, ToParams};
+use super::{application::Application, log::Log, usage::Usage, user::User, ToParams};
 
+use std::borrow::Cow;
+
+/// The OAuth redirection address to be validated by the OAuth endpoints. Apisonator accepts it
+/// either as `redirect_url` or as `redirect_uri`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Redirect<'a> {
+    Url(Cow<'a, str>),
+    Uri(Cow<'a, str>),
+}
+
+impl Redirect<'_> {
+    pub fn key(&self) -> &str {
+        match self {
+            Redirect::Url(_) => "redirect_url",
+            Redirect::Uri(_) => "redirect_uri",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            Redirect::Url(v) | Redirect::Uri(v) => v.as_ref(),
+        }
+    }
+
+    pub fn into_owned(self) -> Redirect<'static> {
+        match self {
+            Redirect::Url(v) => Redirect::Url(v.into_owned().into()),
+            Redirect::Uri(v) => Redirect::Uri(v.into_owned().into()),
+        }
+    }
+}
+
+/// A transaction either borrows its data or owns it, in which case it can be converted into a
+/// `Transaction<'static>` to be stored or moved across threads and tasks.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Transaction<'a> {
-    application: &'a Application,
-    user: Option<&'a User>,
-    usage: Option<&'a Usage<'a>>,
+    application: Cow<'a, Application>,
+    user: Option<Cow<'a, User>>,
+    usage: Option<Cow<'a, Usage<'a>>>,
     timestamp: Option<String>,
+    log: Option<Log<'a>>,
+    referrer: Option<Cow<'a, str>>,
+    redirect: Option<Redirect<'a>>,
 }
 
 impl<'a> Transaction<'a> {
@@ -18,31 +55,158 @@ impl<'a> Transaction<'a> {
         timestamp: Option<i64>,
     ) -> Self {
         Self {
-            application,
-            user,
-            usage,
+            application: Cow::Borrowed(application),
+            user: user.map(Cow::Borrowed),
+            usage: usage.map(Cow::Borrowed),
+            timestamp: timestamp.map(|tsi64| tsi64.to_string()),
+            log: None,
+            referrer: None,
+            redirect: None,
+        }
+    }
+
+    /// Creates a `Transaction` taking ownership of its data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::{application::*, transaction::*, usage::*};
+    ///
+    /// let app = Application::from_user_key("my_user_key");
+    /// let usage = Usage::new(&[("hits", "1")]).into_owned();
+    /// let txn: Transaction<'static> = Transaction::new_owned(app, None, Some(usage), None);
+    /// ```
+    pub fn new_owned(
+        application: Application,
+        user: Option<User>,
+        usage: Option<Usage<'a>>,
+        timestamp: Option<i64>,
+    ) -> Self {
+        Self {
+            application: Cow::Owned(application),
+            user: user.map(Cow::Owned),
+            usage: usage.map(Cow::Owned),
             timestamp: timestamp.map(|tsi64| tsi64.to_string()),
+            log: None,
+            referrer: None,
+            redirect: None,
         }
     }
 
     pub fn application(&self) -> &Application {
-        self.application
+        self.application.as_ref()
     }
 
     pub fn user(&self) -> Option<&User> {
-        self.user
+        self.user.as_deref()
     }
 
     pub fn usage(&self) -> Option<&Usage> {
-        self.usage
+        self.usage.as_deref()
     }
 
     pub fn timestamp(&self) -> Option<&str> {
         self.timestamp.as_deref()
     }
-}
 
-use std::borrow::Cow;
+    // Timestamps are sent as given, which allows keeping the ones parsed from requests as is.
+    pub(crate) fn with_timestamp_str(mut self, timestamp: String) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Attaches traffic log data to the transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::{application::*, log::*, transaction::*};
+    ///
+    /// let app = Application::from_user_key("my_user_key");
+    /// let log = Log::new().with_request("GET /").with_code(200);
+    /// let txn = Transaction::new(&app, None, None, None).with_log(log);
+    /// ```
+    pub fn with_log(mut self, log: Log<'a>) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    pub fn log(&self) -> Option<&Log> {
+        self.log.as_ref()
+    }
+
+    /// Sets the referrer to be checked by referrer filters in authorizations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::{application::*, transaction::*};
+    ///
+    /// let app = Application::from_app_id("my_app_id");
+    /// let txn = Transaction::new(&app, None, None, None)
+    ///     .with_referrer("www.example.com")
+    ///     .with_redirect(Redirect::Url("https://www.example.com/callback".into()));
+    /// ```
+    pub fn with_referrer<S: Into<Cow<'a, str>>>(mut self, referrer: S) -> Self {
+        self.referrer = Some(referrer.into());
+        self
+    }
+
+    /// Sets the redirection address to be validated by OAuth authorizations.
+    pub fn with_redirect(mut self, redirect: Redirect<'a>) -> Self {
+        self.redirect = Some(redirect);
+        self
+    }
+
+    pub fn referrer(&self) -> Option<&str> {
+        self.referrer.as_deref()
+    }
+
+    pub fn redirect(&self) -> Option<&Redirect> {
+        self.redirect.as_ref()
+    }
+
+    // Referrer and redirection parameters only make sense for authorizations, and the latter only
+    // when hitting the OAuth endpoints, so the caller needs to decide when to add them.
+    pub(crate) fn authorization_params_with_mangling<'k, 'v, 'this, E, F>(
+        &'this self,
+        oauth: bool,
+        extendable: &mut E,
+        key_mangling: &mut F,
+    ) where
+        'this: 'k + 'v,
+        E: Extend<(Cow<'k, str>, &'v str)>,
+        F: FnMut(Cow<'k, str>) -> Cow<'k, str>,
+    {
+        let params = [
+            self.referrer().map(|referrer| ("referrer", referrer)),
+            self.redirect()
+                .filter(|_| oauth)
+                .map(|redirect| (redirect.key(), redirect.value())),
+        ];
+
+        extendable.extend(
+            params
+                .iter()
+                .filter_map(|&param| param.map(|(k, v)| (key_mangling(k.into()), v))),
+        );
+    }
+
+    /// Converts the transaction into one owning all of its data, cloning it if needed.
+    pub fn into_owned(self) -> Transaction<'static> {
+        Transaction {
+            application: Cow::Owned(self.application.into_owned()),
+            user: self.user.map(|user| Cow::Owned(user.into_owned())),
+            usage: self
+                .usage
+                .map(|usage| Cow::Owned(usage.into_owned().into_owned())),
+            timestamp: self.timestamp,
+            log: self.log.map(Log::into_owned),
+            referrer: self.referrer.map(|r| r.into_owned().into()),
+            redirect: self.redirect.map(Redirect::into_owned),
+        }
+    }
+}
 
 impl<'k, 'v, 'this, E> ToParams<'k, 'v, 'this, E> for Transaction<'_>
 where
@@ -59,15 +223,19 @@ where
             extendable.extend([(field, ts)].iter().cloned());
         }
 
-        self.application
+        self.application()
             .to_params_with_mangling(extendable, key_mangling);
 
-        if let Some(user_params) = self.user {
+        if let Some(user_params) = self.user() {
             user_params.to_params_with_mangling(extendable, key_mangling);
         }
 
-        if let Some(usage_params) = self.usage {
+        if let Some(usage_params) = self.usage.as_deref() {
             usage_params.to_params_with_mangling(extendable, key_mangling);
         }
+
+        if let Some(log_params) = self.log.as_ref() {
+            log_params.to_params_with_mangling(extendable, key_mangling);
+        }
     }
 }
@@ -1,13 +1,177 @@
 use std::prelude::v1::*;
 
-use crate::ToParams;
+use crate::{anyhow, Error, ToParams};
+
+use std::{
+    borrow::Cow,
+    fmt,
+    ops::{Add, AddAssign},
+    str::FromStr,
+};
+
+/// A typed usage value.
+///
+/// Apisonator either increments the current value of a metric, or sets it to an absolute value
+/// when prefixed with `#`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum UsageValue {
+    Increment(u64),
+    Set(u64),
+}
+
+impl UsageValue {
+    pub fn value(self) -> u64 {
+        match self {
+            UsageValue::Increment(v) | UsageValue::Set(v) => v,
+        }
+    }
+
+    pub fn is_set(self) -> bool {
+        matches!(self, UsageValue::Set(_))
+    }
+
+    /// Merges a usage value with another one that is applied after it, returning `None` on
+    /// overflow.
+    ///
+    /// Note that this is not commutative: a set value discards anything applied before it, and
+    /// increments applied after a set value are added to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::usage::UsageValue;
+    ///
+    /// let value = UsageValue::Increment(5).checked_add(UsageValue::Set(2));
+    /// assert_eq!(value, Some(UsageValue::Set(2)));
+    ///
+    /// let value = UsageValue::Set(2).checked_add(UsageValue::Increment(5));
+    /// assert_eq!(value, Some(UsageValue::Set(7)));
+    /// ```
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        use UsageValue::*;
+
+        match (self, rhs) {
+            (_, Set(v)) => Some(Set(v)),
+            (Increment(a), Increment(b)) => a.checked_add(b).map(Increment),
+            (Set(a), Increment(b)) => a.checked_add(b).map(Set),
+        }
+    }
+}
+
+impl Default for UsageValue {
+    fn default() -> Self {
+        UsageValue::Increment(0)
+    }
+}
+
+impl From<u64> for UsageValue {
+    fn from(v: u64) -> Self {
+        UsageValue::Increment(v)
+    }
+}
+
+impl core::convert::TryFrom<i64> for UsageValue {
+    type Error = Error;
+
+    fn try_from(v: i64) -> Result<Self, Self::Error> {
+        if v < 0 {
+            Err(anyhow!("usage values cannot be negative: {}", v))
+        } else {
+            Ok(UsageValue::Increment(v as u64))
+        }
+    }
+}
+
+// Saturates on overflow. See `checked_add` for the semantics.
+impl Add for UsageValue {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        use UsageValue::*;
+
+        match (self, rhs) {
+            (_, Set(v)) => Set(v),
+            (Increment(a), Increment(b)) => Increment(a.saturating_add(b)),
+            (Set(a), Increment(b)) => Set(a.saturating_add(b)),
+        }
+    }
+}
+
+impl AddAssign for UsageValue {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl FromStr for UsageValue {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (digits, set) = match s.as_bytes().first() {
+            Some(b'#') => (&s[1..], true),
+            _ => (s, false),
+        };
+
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(anyhow!("invalid usage value {:?}", s));
+        }
+
+        let value = digits
+            .parse::<u64>()
+            .map_err(|e| anyhow!("invalid usage value {:?}: {}", s, e))?;
+
+        Ok(if set {
+            UsageValue::Set(value)
+        } else {
+            UsageValue::Increment(value)
+        })
+    }
+}
+
+impl fmt::Display for UsageValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsageValue::Increment(v) => write!(f, "{}", v),
+            UsageValue::Set(v) => write!(f, "#{}", v),
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct MetricUsage<'m>(&'m str, &'m str);
+pub struct MetricUsage<'m>(Cow<'m, str>, Cow<'m, str>);
 
 impl<'m, M: AsRef<str> + 'm, V: AsRef<str> + 'm> From<&'m (M, V)> for MetricUsage<'m> {
     fn from((m, v): &'m (M, V)) -> Self {
-        Self(m.as_ref(), v.as_ref())
+        Self(m.as_ref().into(), v.as_ref().into())
+    }
+}
+
+impl<'m> MetricUsage<'m> {
+    pub fn new<M: Into<Cow<'m, str>>, V: Into<Cow<'m, str>>>(metric: M, value: V) -> Self {
+        Self(metric.into(), value.into())
+    }
+
+    pub fn metric(&self) -> &str {
+        self.0.as_ref()
+    }
+
+    /// Creates a `MetricUsage` from a typed value, which is always valid.
+    pub fn from_value<M: Into<Cow<'m, str>>>(metric: M, value: UsageValue) -> Self {
+        Self(metric.into(), value.to_string().into())
+    }
+
+    pub fn value(&self) -> &str {
+        self.1.as_ref()
+    }
+
+    /// Parses the value into a typed usage value.
+    pub fn usage_value(&self) -> Result<UsageValue, Error> {
+        self.value().parse()
+    }
+
+    /// Takes ownership of the metric and value, cloning them if needed.
+    pub fn into_owned(self) -> MetricUsage<'static> {
+        MetricUsage(self.0.into_owned().into(), self.1.into_owned().into())
     }
 }
 
@@ -39,6 +203,73 @@ impl<'m> Usage<'m> {
         Self::from(mv)
     }
 
+    /// Creates a `Usage` checking that all metric names and values are valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::usage::*;
+    ///
+    /// assert!(Usage::try_new(&[("hits", "1"), ("storage", "#10")]).is_ok());
+    /// assert!(Usage::try_new(&[("hits", "-1")]).is_err());
+    /// assert!(Usage::try_new(&[("hits", "abc")]).is_err());
+    /// ```
+    pub fn try_new<M: AsRef<str> + 'm, V: AsRef<str> + 'm>(
+        mv: &'m [(M, V)],
+    ) -> Result<Self, Error> {
+        let usage = Self::from(mv);
+
+        for mu in usage.0.iter() {
+            if mu.metric().is_empty() {
+                return Err(anyhow!("empty metric name"));
+            }
+            mu.usage_value()?;
+        }
+
+        Ok(usage)
+    }
+
+    /// Merges another `Usage` applied after this one, combining the values of the same metrics.
+    ///
+    /// Fails if any of the values is not valid or the result overflows, in which case this
+    /// `Usage` is left unmodified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::usage::*;
+    ///
+    /// let first = [("hits", "1"), ("storage", "#10")];
+    /// let second = [("hits", "2"), ("storage", "5"), ("other", "3")];
+    ///
+    /// let mut usage = Usage::new(&first);
+    /// usage.merge(&Usage::new(&second)).unwrap();
+    ///
+    /// assert_eq!(usage, Usage::new(&[("hits", "3"), ("storage", "#15"), ("other", "3")]));
+    /// ```
+    pub fn merge(&mut self, other: &Usage<'m>) -> Result<(), Error> {
+        let mut merged = self.0.clone();
+
+        for mu in other.0.iter() {
+            let value = mu.usage_value()?;
+
+            match merged.iter_mut().find(|m| m.metric() == mu.metric()) {
+                Some(existing) => {
+                    let sum = existing
+                        .usage_value()?
+                        .checked_add(value)
+                        .ok_or_else(|| anyhow!("usage value overflow in {}", mu.metric()))?;
+                    existing.1 = sum.to_string().into();
+                }
+                None => merged.push(mu.clone()),
+            }
+        }
+
+        self.0 = merged;
+
+        Ok(())
+    }
+
     /// Consumes the Usage and returns the underlying vector containing metric-value references.
     pub fn into_inner(self) -> Vec<MetricUsage<'m>> {
         self.0
@@ -53,9 +284,36 @@ impl<'m> Usage<'m> {
     pub fn as_mut_vec(&mut self) -> &mut Vec<MetricUsage<'m>> {
         self.0.as_mut()
     }
+
+    /// Converts the Usage into one that owns its metrics and values so that it can be stored
+    /// or sent without keeping the original data around.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use threescalers::usage::*;
+    ///
+    /// let usage: Usage<'static> = {
+    ///     let metrics = vec![("metric1".to_string(), "10".to_string())];
+    ///     Usage::new(metrics.as_slice()).into_owned()
+    /// };
+    /// ```
+    pub fn into_owned(self) -> Usage<'static> {
+        Usage(self.0.into_iter().map(MetricUsage::into_owned).collect())
+    }
+}
+
+impl<'m> From<Vec<MetricUsage<'m>>> for Usage<'m> {
+    fn from(v: Vec<MetricUsage<'m>>) -> Self {
+        Self(v)
+    }
 }
 
-use std::borrow::Cow;
+impl<'m> core::iter::FromIterator<MetricUsage<'m>> for Usage<'m> {
+    fn from_iter<T: IntoIterator<Item = MetricUsage<'m>>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
 
 impl<'k, 'v, 'this, E> ToParams<'k, 'v, 'this, E> for Usage<'this>
 where
@@ -69,7 +327,7 @@ where
     ) {
         extendable.extend(self.0.iter().map(|mv| {
             let m = format!("usage[{}]", mv.0);
-            (key_mangling(m.into()), mv.1)
+            (key_mangling(m.into()), mv.1.as_ref())
         }))
     }
 }
@@ -115,4 +373,80 @@ mod tests {
         ];
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn parse_usage_values() {
+        assert_eq!(UsageValue::Increment(10), "10".parse().unwrap());
+        assert_eq!(UsageValue::Set(0), "#0".parse().unwrap());
+
+        for invalid in [
+            "",
+            "#",
+            "-1",
+            "+1",
+            "abc",
+            "1.5",
+            "# 1",
+            "18446744073709551616",
+        ]
+        .iter()
+        {
+            assert!(invalid.parse::<UsageValue>().is_err());
+        }
+    }
+
+    #[test]
+    fn add_usage_values() {
+        use UsageValue::*;
+
+        assert_eq!(Increment(3), Increment(1) + Increment(2));
+        assert_eq!(Set(2), Increment(1) + Set(2));
+        assert_eq!(Set(3), Set(1) + Increment(2));
+        let max = 0u64.wrapping_sub(1);
+        assert_eq!(Increment(max), Increment(max) + Increment(1));
+        assert_eq!(None, Set(max).checked_add(Increment(1)));
+    }
+
+    #[test]
+    fn to_params_from_typed_values() {
+        let usage = vec![
+            MetricUsage::from_value("metric1", UsageValue::Increment(10)),
+            MetricUsage::from_value("metric2", UsageValue::Set(20)),
+        ]
+        .into_iter()
+        .collect::<Usage>();
+
+        let mut result = Vec::new();
+        usage.to_params(&mut result);
+
+        let expected: Vec<(Cow<str>, &str)> = vec![
+            ("usage[metric1]".into(), "10"),
+            ("usage[metric2]".into(), "#20"),
+        ];
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn merge_fails_without_modifying_usage() {
+        let metrics = [("hits", "1")];
+        let mut usage = Usage::new(&metrics);
+        let invalid = [("other", "2"), ("hits", "x")];
+
+        assert!(usage.merge(&Usage::new(&invalid)).is_err());
+        assert_eq!(Usage::new(&metrics), usage);
+    }
+
+    #[test]
+    fn to_params_from_owned() {
+        let usage = {
+            let metrics = vec![(String::from("metric1"), String::from("10"))];
+            Usage::from(metrics.as_slice()).into_owned()
+        };
+
+        let mut result = Vec::new();
+        usage.to_params(&mut result);
+
+        let expected: Vec<(Cow<str>, &str)> = vec![("usage[metric1]".into(), "10")];
+        assert_eq!(expected, result);
+    }
 }
@@ -3,7 +3,7 @@ use threescalers::{api_call::*, application::*, credentials::*, service::*, user
 use std::collections::HashMap;
 
 use crate::helpers::*;
-use threescalers::transaction::Transaction;
+use threescalers::{http::Request, transaction::Transaction, usage::Usage};
 
 #[test]
 fn returns_auth_request_from_service_id_pkey_and_app_id() {
@@ -14,7 +14,7 @@ fn returns_auth_request_from_service_id_pkey_and_app_id() {
     let app_id = "an_app_id";
     let app = Application::from_app_id(app_id);
     let txn = [Transaction::new(&app, None, None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -39,7 +39,7 @@ fn returns_auth_request_from_service_id_pkey_app_id_and_user_id() {
     let user_id = "a_user_id";
     let user = User::from_user_id(user_id);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -65,7 +65,7 @@ fn returns_auth_request_from_service_id_pkey_app_id_and_oauth_user() {
     let user_oauth_token = "a_user_token";
     let user = User::from_oauth_token(user_oauth_token);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -90,7 +90,7 @@ fn returns_auth_request_from_service_id_pkey_app_id_and_app_key() {
     let app_key = "an_app_key";
     let app = Application::from_app_id_and_key(app_id, app_key);
     let txn = [Transaction::new(&app, None, None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -117,7 +117,7 @@ fn returns_auth_request_from_service_id_pkey_app_id_app_key_and_user_id() {
     let user_id = "a_user_id";
     let user = User::from_user_id(user_id);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -145,7 +145,7 @@ fn returns_auth_request_from_service_id_pkey_app_id_app_key_and_oauth_user() {
     let user_oauth_token = "a_user_token";
     let user = User::from_oauth_token(user_oauth_token);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -170,7 +170,7 @@ fn returns_auth_request_from_service_id_pkey_and_user_key() {
     let user_key = "a_user_key";
     let app = Application::from_user_key(user_key);
     let txn = [Transaction::new(&app, None, None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -195,7 +195,7 @@ fn returns_auth_request_from_service_id_pkey_user_key_and_user_id() {
     let user_id = "a_user_id";
     let user = User::from_user_id(user_id);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -221,7 +221,7 @@ fn returns_auth_request_from_service_id_pkey_user_key_and_oauth_user() {
     let user_oauth_token = "a_user_token";
     let user = User::from_oauth_token(user_oauth_token);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -245,7 +245,7 @@ fn returns_auth_request_from_service_id_pkey_and_oauth_token() {
     let oauth_token = "an_app_token";
     let app = Application::from_oauth_token(oauth_token);
     let txn = [Transaction::new(&app, None, None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -270,7 +270,7 @@ fn returns_auth_request_from_service_id_pkey_oauth_token_and_user_id() {
     let user_id = "a_user_id";
     let user = User::from_user_id(user_id);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("provider_key", provider_key);
@@ -301,7 +301,7 @@ fn returns_auth_request_from_service_id_token_and_app_id() {
     let app_id = "an_app_id";
     let app = Application::from_app_id(app_id);
     let txn = [Transaction::new(&app, None, None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -326,7 +326,7 @@ fn returns_auth_request_from_service_id_token_app_id_and_user_id() {
     let user_id = "a_user_id";
     let user = User::from_user_id(user_id);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -352,7 +352,7 @@ fn returns_auth_request_from_service_id_token_app_id_and_oauth_user() {
     let user_oauth_token = "a_user_token";
     let user = User::from_oauth_token(user_oauth_token);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -377,7 +377,7 @@ fn returns_auth_request_from_service_id_token_app_id_and_app_key() {
     let app_key = "an_app_key";
     let app = Application::from_app_id_and_key(app_id, app_key);
     let txn = [Transaction::new(&app, None, None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -404,7 +404,7 @@ fn returns_auth_request_from_service_id_token_app_id_app_key_and_user_id() {
     let user_id = "a_user_id";
     let user = User::from_user_id(user_id);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -432,7 +432,7 @@ fn returns_auth_request_from_service_id_token_app_id_app_key_and_oauth_user() {
     let user_oauth_token = "a_user_token";
     let user = User::from_oauth_token(user_oauth_token);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -457,7 +457,7 @@ fn returns_auth_request_from_service_id_token_and_user_key() {
     let user_key = "a_user_key";
     let app = Application::from_user_key(user_key);
     let txn = [Transaction::new(&app, None, None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -482,7 +482,7 @@ fn returns_auth_request_from_service_id_token_user_key_and_user_id() {
     let user_id = "a_user_id";
     let user = User::from_user_id(user_id);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -508,7 +508,7 @@ fn returns_auth_request_from_service_id_token_user_key_and_oauth_user() {
     let oauth_user_token = "a_user_token";
     let user = User::from_oauth_token(oauth_user_token);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -532,7 +532,7 @@ fn returns_auth_request_from_service_id_token_and_oauth_token() {
     let oauth_token = "an_app_token";
     let app = Application::from_oauth_token(oauth_token);
     let txn = [Transaction::new(&app, None, None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -557,7 +557,7 @@ fn returns_auth_request_from_service_id_token_oauth_token_and_user_id() {
     let user_id = "a_user_id";
     let user = User::from_user_id(user_id);
     let txn = [Transaction::new(&app, Some(&user), None, None)];
-    let call = ApiCall::new(Kind::Authorize, &service, &txn, None);
+    let call = ApiCall::new(Kind::Authorize, &service, &txn, None).unwrap();
 
     let mut expected_params = HashMap::new();
     expected_params.insert("service_token", service_token);
@@ -579,6 +579,323 @@ fn returns_auth_request_from_service_id_token_oauth_token_and_oauth_user() {
     // using a token for the app and another for the user.
 }
 
+#[test]
+fn builds_authrep_call_with_a_single_transaction() {
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let app = Application::from_user_key("a_user_key");
+    let txn = Transaction::new(&app, None, None, None);
+    let call = ApiCall::builder(&service)
+        .authrep()
+        .transaction(&txn)
+        .build();
+
+    assert_eq!(Kind::AuthRep, call.kind());
+    assert_eq!(Some(&txn), call.transaction());
+    assert_eq!(None, call.extensions());
+}
+
+#[test]
+fn builds_report_call_with_multiple_transactions() {
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let apps = [
+        Application::from_user_key("a_user_key"),
+        Application::from_app_id("an_app_id"),
+    ];
+    let txns = apps
+        .iter()
+        .map(|app| Transaction::new(app, None, None, None))
+        .collect::<Vec<_>>();
+    let extensions = threescalers::extensions::List::new().no_body();
+    let call = ApiCall::builder(&service)
+        .report()
+        .transactions(&txns)
+        .expect("failed to set report transactions")
+        .extensions(&extensions)
+        .build();
+
+    assert_eq!(Kind::Report, call.kind());
+    assert_eq!(txns.as_slice(), call.transactions());
+    assert_eq!(Some(&extensions), call.extensions());
+}
+
+#[test]
+fn refuses_report_call_without_transactions() {
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let builder = ApiCall::builder(&service).report().transactions(&[]);
+
+    assert!(builder.is_err());
+}
+
+#[test]
+fn refuses_calls_with_an_invalid_number_of_transactions() {
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let app = Application::from_user_key("a_user_key");
+    let txns = [
+        Transaction::new(&app, None, None, None),
+        Transaction::new(&app, None, None, None),
+    ];
+
+    for &kind in [Kind::Authorize, Kind::AuthRep].iter() {
+        assert!(ApiCall::new(kind, &service, &txns, None).is_err());
+        assert!(ApiCall::new(kind, &service, &[], None).is_err());
+        assert!(ApiCall::new_owned(kind, service.clone(), txns.to_vec(), None).is_err());
+    }
+
+    assert!(ApiCall::new(Kind::Report, &service, &[], None).is_err());
+    assert!(ApiCall::new_owned(Kind::Report, service.clone(), vec![], None).is_err());
+    assert!(ApiCall::new(Kind::Report, &service, &txns, None).is_ok());
+}
+
+#[test]
+fn round_trips_encoded_parameters_for_every_kind() {
+    let creds = Credentials::from_token("12[3]token +&=%");
+    let service = Service::new("a service/id?", creds);
+    let app = Application::from_app_id_and_key("an app&id=1", "kéy#[0]");
+    let user = User::from_user_id("userkey 4;+");
+    let metrics = [("hits", "1"), ("a metric", "2")];
+    let usage = Usage::new(&metrics);
+    let txn = [Transaction::new(&app, Some(&user), Some(&usage), None)];
+
+    for &kind in [Kind::Authorize, Kind::AuthRep, Kind::Report].iter() {
+        let call = ApiCall::new(kind, &service, &txn, None).unwrap();
+        let request = Request::from(&call);
+        let encoded = request
+            .parameters
+            .query()
+            .or_else(|| request.parameters.body())
+            .unwrap();
+
+        let expected = call
+            .params()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(expected, decode_params(encoded), "kind {:?}", kind);
+    }
+}
+
+#[test]
+fn owned_call_produces_the_same_request_as_borrowed_call() {
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let app = Application::from_app_id_and_key("an_app_id", "an_app_key");
+    let user = User::from_user_id("a_user_id");
+    let metrics = [("hits", "1"), ("other", "2")];
+    let usage = Usage::new(&metrics);
+    let txns = [
+        Transaction::new(&app, Some(&user), Some(&usage), Some(1_600_000_000)),
+        Transaction::new(&app, None, None, None),
+    ];
+    let extensions = threescalers::extensions::List::new().hierarchy();
+    let call = ApiCall::new(Kind::Report, &service, &txns, Some(&extensions)).unwrap();
+    let expected = Request::from(&call);
+
+    let owned: ApiCall<'static> = call.clone().into_owned();
+    let request = std::thread::spawn(move || Request::from(&owned))
+        .join()
+        .unwrap();
+
+    assert_eq!(expected.method, request.method);
+    assert_eq!(expected.path, request.path);
+    assert_eq!(expected.parameters, request.parameters);
+    assert_eq!(expected.headers, request.headers);
+}
+
+#[test]
+fn report_call_includes_transaction_logs() {
+    use threescalers::log::Log;
+
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let app = Application::from_user_key("a_user_key");
+    let txns = [
+        Transaction::new(&app, None, None, None)
+            .with_log(Log::new().with_request("GET /").with_code(200)),
+        Transaction::new(&app, None, None, None),
+    ];
+    let call = ApiCall::new(Kind::Report, &service, &txns, None).unwrap();
+
+    let mut expected_params = HashMap::new();
+    expected_params.insert("service_token", "a_service_token");
+    expected_params.insert("service_id", "a_service_id");
+    expected_params.insert("transactions[0]user_key", "a_user_key");
+    expected_params.insert("transactions[0]log[request]", "GET /");
+    expected_params.insert("transactions[0]log[code]", "200");
+    expected_params.insert("transactions[1]user_key", "a_user_key");
+
+    assert_eq!(expected_params, vec_to_hash(&call.params()));
+}
+
+#[test]
+fn authorization_calls_include_referrer_and_oauth_redirect() {
+    use threescalers::transaction::Redirect;
+
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let app = Application::from_app_id("an_app_id");
+    let oauth_app = Application::from_oauth_token("a_token");
+    let redirect_url = "https://example.com/callback";
+
+    for &kind in [Kind::Authorize, Kind::AuthRep].iter() {
+        let txn = [Transaction::new(&app, None, None, None)
+            .with_referrer("example.com")
+            .with_redirect(Redirect::Url(redirect_url.into()))];
+        let call = ApiCall::new(kind, &service, &txn, None).unwrap();
+        let params = call.params();
+        let params = vec_to_hash(&params);
+
+        assert_eq!(Some(&"example.com"), params.get("referrer"));
+        assert_eq!(None, params.get("redirect_url"));
+
+        let txn = [Transaction::new(&oauth_app, None, None, None)
+            .with_referrer("example.com")
+            .with_redirect(Redirect::Uri(redirect_url.into()))];
+        let call = ApiCall::new(kind, &service, &txn, None).unwrap();
+        let params = call.params();
+        let params = vec_to_hash(&params);
+
+        assert!(Request::from(&call).path.contains("oauth_"));
+        assert_eq!(Some(&"example.com"), params.get("referrer"));
+        assert_eq!(Some(&redirect_url), params.get("redirect_uri"));
+    }
+}
+
+#[test]
+fn report_calls_skip_referrer_and_oauth_redirect() {
+    use threescalers::transaction::Redirect;
+
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let app = Application::from_oauth_token("a_token");
+    let txn = [Transaction::new(&app, None, None, None)
+        .with_referrer("example.com")
+        .with_redirect(Redirect::Url("https://example.com/callback".into()))];
+    let call = ApiCall::new(Kind::Report, &service, &txn, None).unwrap();
+
+    let mut expected_params = HashMap::new();
+    expected_params.insert("service_token", "a_service_token");
+    expected_params.insert("service_id", "a_service_id");
+    expected_params.insert("transactions[0]access_token", "a_token");
+
+    assert_eq!(expected_params, vec_to_hash(&call.params()));
+}
+
+#[test]
+fn requests_parse_back_into_calls_losslessly() {
+    use std::convert::TryFrom;
+    use threescalers::{extensions::List, log::Log, transaction::Redirect};
+
+    let service = Service::new("a service/id?", Credentials::from_key("12[3]key +&=%"));
+    let apps = [
+        Application::from_app_id_and_key("an app&id=1", "kéy#[0]"),
+        Application::from_user_key("a_user_key"),
+        Application::from_oauth_token("an_app_token"),
+    ];
+    let user = User::from_oauth_token("a user+token");
+    let metrics = [("hits", "1"), ("a [metric]", "#2")];
+    let usage = Usage::new(&metrics);
+    let extensions = List::new()
+        .no_body()
+        .flat_usage(1)
+        .push_other("a&b".into(), "c=d".into());
+
+    for app in apps.iter() {
+        let txns = [
+            Transaction::new(app, Some(&user), Some(&usage), Some(1_600_000_000))
+                .with_log(Log::new().with_request("GET / HTTP/1.1").with_code(200))
+                .with_referrer("example.com")
+                .with_redirect(Redirect::Uri("https://example.com/?a=b&c".into())),
+            Transaction::new(app, None, None, None),
+        ];
+
+        for &kind in [Kind::Authorize, Kind::AuthRep, Kind::Report].iter() {
+            let txns = if kind.is_report() {
+                &txns[..]
+            } else {
+                &txns[..1]
+            };
+            let call = ApiCall::new(kind, &service, txns, Some(&extensions)).unwrap();
+            let request = Request::from(&call);
+
+            let (uri, body) = request.uri_and_body();
+            let parsed = Request::from_parts(request.method, &uri, body, request.headers.clone())
+                .and_then(|r| ApiCall::try_from(&r))
+                .unwrap();
+            let reencoded = Request::from(&parsed);
+
+            assert_eq!(request.method, reencoded.method, "kind {:?}", kind);
+            assert_eq!(request.path, reencoded.path, "kind {:?}", kind);
+            assert_eq!(request.parameters, reencoded.parameters, "kind {:?}", kind);
+            assert_eq!(request.headers, reencoded.headers, "kind {:?}", kind);
+        }
+    }
+}
+
+#[test]
+fn requests_use_configured_endpoints() {
+    use threescalers::http::{endpoints::*, HeaderMap};
+
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let app = Application::from_oauth_token("a_token");
+    let txn = Transaction::new(&app, None, None, None);
+    let call = ApiCall::builder(&service)
+        .authrep()
+        .transaction(&txn)
+        .build();
+    let endpoints = Endpoints::new()
+        .with_base_path("/backend")
+        .with_path(Endpoint::OAuthAuthRep, "/oauth_authrep.json");
+
+    let request = Request::from_api_call(&call, &endpoints);
+
+    assert_eq!("/backend/oauth_authrep.json", request.path);
+    assert_eq!(Request::from(&call).parameters, request.parameters);
+
+    let (uri, body) = request.uri_and_body();
+    let parsed = Request::from_parts_with_endpoints(
+        request.method,
+        &uri,
+        body,
+        HeaderMap::new(),
+        &endpoints,
+    )
+    .and_then(|r| ApiCall::from_request(&r, &endpoints))
+    .unwrap();
+
+    assert_eq!(call.into_owned(), parsed);
+    assert!(Request::from_parts(request.method, &uri, body, HeaderMap::new()).is_err());
+}
+
+#[test]
+fn validates_oauth_user_requires_oauth_app() {
+    let service = Service::new("a_service_id", Credentials::from_token("a_service_token"));
+    let user = User::from_oauth_token("a_user_token");
+    let app = Application::from_app_id("an_app_id");
+    let oauth_app = Application::from_oauth_token("an_app_token");
+    let invalid = Transaction::new(&app, Some(&user), None, None);
+    let valid = Transaction::new(&oauth_app, Some(&user), None, None);
+
+    let error = ApiCall::builder(&service)
+        .authorize()
+        .transaction(&invalid)
+        .build()
+        .validate()
+        .unwrap_err();
+
+    assert_eq!(
+        &[ValidationIssue::OAuthUserWithoutOAuthApplication { transaction: 0 }],
+        error.issues()
+    );
+    assert_eq!(
+        "invalid API call: access_token: OAuth user used with a non-OAuth application",
+        error.to_string()
+    );
+
+    let call = ApiCall::builder(&service)
+        .authorize()
+        .transaction(&valid)
+        .build();
+
+    assert_eq!(Ok(()), call.validate());
+}
+
 mod helpers {
     use std::{borrow::Cow, collections::HashMap};
 
@@ -589,4 +906,21 @@ mod helpers {
         }
         h
     }
+
+    pub fn decode_params(params: &str) -> Vec<(String, String)> {
+        use percent_encoding::percent_decode_str;
+
+        let decode = |s| percent_decode_str(s).decode_utf8().unwrap().into_owned();
+
+        params
+            .split('&')
+            .map(|kv| {
+                let mut it = kv.splitn(2, '=');
+                let (k, v) = (it.next().unwrap(), it.next().unwrap());
+                assert!(!k.contains(&['&', '=', '+', ' '][..]));
+                assert!(!v.contains(&['&', '=', '+', ' ', '[', ']'][..]));
+                (decode(k), decode(v))
+            })
+            .collect()
+    }
 }